@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS notification_records (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    message_id TEXT NOT NULL,
+    cluster_name TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    notified_at TEXT NOT NULL,
+    destination TEXT NOT NULL,
+    success BOOLEAN NOT NULL,
+    response TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS notification_records_cluster_name_notified_at
+    ON notification_records (cluster_name, notified_at);
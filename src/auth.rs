@@ -0,0 +1,52 @@
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use subtle::ConstantTimeEq;
+
+use crate::AppState;
+
+/// Requires an `Authorization: Bearer <token>` header matching `API_TOKEN`,
+/// responding with HTTP 401 otherwise. When `API_TOKEN` isn't set, all
+/// requests are allowed, as Pub/Sub push subscriptions can't send the header.
+pub async fn require_api_token(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if let Some(api_token) = &state.config.load().api_token {
+        if !has_token(req.headers(), api_token) {
+            return unauthorized();
+        }
+    }
+
+    next.run(req).await
+}
+
+/// Like `require_api_token`, but for the management APIs, which respond with
+/// HTTP 404 as if they didn't exist when `API_TOKEN` isn't set.
+pub async fn require_management_token(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    match &state.config.load().api_token {
+        None => StatusCode::NOT_FOUND.into_response(),
+        Some(api_token) if !has_token(req.headers(), api_token) => unauthorized(),
+        Some(_) => next.run(req).await,
+    }
+}
+
+fn has_token(headers: &HeaderMap, api_token: &str) -> bool {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+
+    bool::from(token.as_bytes().ct_eq(api_token.as_bytes()))
+}
+
+fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")]).into_response()
+}
@@ -0,0 +1,121 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tracing::error;
+
+/// The number of messages which can be queued before `push` waits for a
+/// batch to be flushed.
+const CHANNEL_CAPACITY: usize = 1000;
+
+/// Collects messages arriving within `window` of the first one, or until
+/// there are `max_size` of them, and passes them to `flush` together. Used
+/// to post a single consolidated Slack message under heavy load.
+#[derive(Debug)]
+pub struct MessageBatcher<T> {
+    sender: mpsc::Sender<T>,
+}
+
+// Derived `Clone` would require `T: Clone`
+impl<T> Clone for MessageBatcher<T> {
+    fn clone(&self) -> Self {
+        Self { sender: self.sender.clone() }
+    }
+}
+
+impl<T: Send + 'static> MessageBatcher<T> {
+    /// Spawns the task collecting and flushing batches. Remaining messages
+    /// are flushed once all clones of the batcher have been dropped.
+    pub fn spawn<F, Fut>(window: Duration, max_size: usize, flush: F) -> Self
+    where
+        F: Fn(Vec<T>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let (sender, mut receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let max_size = max_size.max(1);
+
+        tokio::spawn(async move {
+            while let Some(first) = receiver.recv().await {
+                let mut batch = vec![first];
+
+                let deadline = tokio::time::sleep(window);
+                tokio::pin!(deadline);
+
+                while batch.len() < max_size {
+                    tokio::select! {
+                        _ = &mut deadline => break,
+                        received = receiver.recv() => match received {
+                            Some(message) => batch.push(message),
+                            None => break,
+                        },
+                    }
+                }
+
+                flush(batch).await;
+            }
+        });
+
+        Self { sender }
+    }
+
+    pub async fn push(&self, message: T) {
+        if self.sender.send(message).await.is_err() {
+            error!("message batcher stopped, dropping message");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    fn batcher(
+        window: Duration,
+        max_size: usize,
+    ) -> (MessageBatcher<u32>, mpsc::Receiver<Vec<u32>>) {
+        let (flushed, receiver) = mpsc::channel(10);
+        let batcher = MessageBatcher::spawn(window, max_size, move |batch| {
+            let flushed = flushed.clone();
+            async move { flushed.send(batch).await.unwrap() }
+        });
+
+        (batcher, receiver)
+    }
+
+    #[tokio::test]
+    async fn window() {
+        let (batcher, mut flushed) = batcher(Duration::from_millis(50), 10);
+        for message in 1..=3 {
+            batcher.push(message).await;
+        }
+
+        assert_eq!(flushed.recv().await.unwrap(), [1, 2, 3]);
+
+        batcher.push(4).await;
+        assert_eq!(flushed.recv().await.unwrap(), [4]);
+    }
+
+    #[tokio::test]
+    async fn max_size() {
+        let (batcher, mut flushed) = batcher(Duration::from_secs(60), 2);
+        for message in 1..=3 {
+            batcher.push(message).await;
+        }
+
+        // Full batches are flushed without waiting for the window to pass
+        let batch = timeout(Duration::from_secs(1), flushed.recv()).await.unwrap();
+        assert_eq!(batch.unwrap(), [1, 2]);
+        assert!(timeout(Duration::from_millis(50), flushed.recv()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn dropped() {
+        let (batcher, mut flushed) = batcher(Duration::from_secs(60), 10);
+        batcher.push(1).await;
+        drop(batcher);
+
+        let batch = timeout(Duration::from_secs(1), flushed.recv()).await.unwrap();
+        assert_eq!(batch.unwrap(), [1]);
+    }
+}
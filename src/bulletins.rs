@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+
+use crate::message::attributes::payload::{SecurityBulletinEvent, Severity, UpgradeAvailableEvent};
+
+const CAPACITY: NonZeroUsize = NonZeroUsize::new(100).unwrap();
+
+/// Remembers the patched versions of recently received security bulletins,
+/// so `UpgradeAvailableEvent` messages for versions patching them can be
+/// marked as security patches.
+#[derive(Clone, Debug)]
+pub struct KnownBulletins {
+    patched_versions: Arc<Mutex<LruCache<String, Vec<String>>>>,
+}
+
+impl Default for KnownBulletins {
+    fn default() -> Self {
+        Self { patched_versions: Arc::new(Mutex::new(LruCache::new(CAPACITY))) }
+    }
+}
+
+impl KnownBulletins {
+    pub fn record(&self, bulletin: &SecurityBulletinEvent) {
+        if bulletin.bulletin_id.is_empty() || bulletin.patched_versions.is_empty() {
+            return;
+        }
+
+        let mut patched_versions = self.patched_versions.lock().unwrap();
+        patched_versions.put(bulletin.bulletin_id.clone(), bulletin.patched_versions.clone());
+    }
+
+    /// Returns the IDs of the known bulletins patched by the available
+    /// version, sorted.
+    pub fn patched_by(&self, event: &UpgradeAvailableEvent) -> Vec<String> {
+        let patched_versions = self.patched_versions.lock().unwrap();
+        let mut bulletin_ids: Vec<_> = patched_versions
+            .iter()
+            .filter(|(_, versions)| event.is_security_patch(versions))
+            .map(|(bulletin_id, _)| bulletin_id.clone())
+            .collect();
+
+        bulletin_ids.sort();
+        bulletin_ids
+    }
+}
+
+/// Whether a bulletin is more severe than the recent ones, see
+/// `SeverityHistory::record`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SeverityTrend {
+    Increasing,
+    Decreasing,
+}
+
+impl Display for SeverityTrend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SeverityTrend::Increasing => "↑ severity increasing",
+            SeverityTrend::Decreasing => "↓ severity decreasing",
+        })
+    }
+}
+
+/// The severities of the last `window` security bulletins.
+#[derive(Clone, Debug)]
+pub struct SeverityHistory {
+    severities: Arc<Mutex<VecDeque<u8>>>,
+    window: usize,
+}
+
+impl SeverityHistory {
+    pub fn new(window: usize) -> Self {
+        Self { severities: Arc::new(Mutex::new(VecDeque::with_capacity(window))), window }
+    }
+
+    /// Records the severity of a bulletin and returns how it compares to the
+    /// average of the previous ones, or `None` when there are none or the
+    /// severity isn't known.
+    pub fn record(&self, bulletin: &SecurityBulletinEvent) -> Option<SeverityTrend> {
+        let severity = match bulletin.severity() {
+            Severity::Unknown => return None,
+            severity => severity as u8,
+        };
+        if self.window == 0 {
+            return None;
+        }
+
+        let mut severities = self.severities.lock().unwrap();
+        let trend = (!severities.is_empty()).then(|| {
+            let total: u32 = severities.iter().map(|&s| u32::from(s)).sum();
+            match u32::from(severity) * severities.len() as u32 > total {
+                true => SeverityTrend::Increasing,
+                false => SeverityTrend::Decreasing,
+            }
+        });
+
+        if severities.len() == self.window {
+            severities.pop_front();
+        }
+        severities.push_back(severity);
+
+        trend
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bulletin(bulletin_id: &str, patched_versions: &[&str]) -> SecurityBulletinEvent {
+        SecurityBulletinEvent {
+            bulletin_id: bulletin_id.to_string(),
+            patched_versions: patched_versions.iter().map(|v| v.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn severity(severity: &str) -> SecurityBulletinEvent {
+        SecurityBulletinEvent { severity: severity.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn severity_trend() {
+        let history = SeverityHistory::new(2);
+
+        assert_eq!(history.record(&severity("Medium")), None);
+        assert_eq!(history.record(&severity("High")), Some(SeverityTrend::Increasing));
+        assert_eq!(history.record(&severity("Medium")), Some(SeverityTrend::Decreasing));
+        assert_eq!(history.record(&severity("Unknown")), None);
+        // Only [High, Medium] are within the window, averaging 2.5
+        assert_eq!(history.record(&severity("High")), Some(SeverityTrend::Increasing));
+        // [Medium, High]
+        assert_eq!(history.record(&severity("low")), Some(SeverityTrend::Decreasing));
+    }
+
+    #[test]
+    fn patched_by() {
+        let bulletins = KnownBulletins::default();
+        bulletins.record(&bulletin("GCP-2022-005", &["1.21.9-gke.200", "1.22.6-gke.600"]));
+        bulletins.record(&bulletin("GCP-2022-002", &["1.22.4-gke.1501"]));
+        bulletins.record(&bulletin("GCP-2022-008", &[]));
+
+        let event = |version: &str| UpgradeAvailableEvent {
+            version: version.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            bulletins.patched_by(&event("1.22.6-gke.600")),
+            ["GCP-2022-002", "GCP-2022-005"]
+        );
+        assert_eq!(bulletins.patched_by(&event("1.22.6-gke.300")), ["GCP-2022-002"]);
+        assert!(bulletins.patched_by(&event("1.23.1-gke.100")).is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+
+use crate::error::AppError;
+
+/// How long a fetched changelog is reused, rather than fetching it for every
+/// `UpgradeAvailableEvent`.
+const CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// How long a failed fetch is remembered, so an unavailable changelog isn't
+/// fetched (and the failure logged) for every `UpgradeAvailableEvent`.
+const FAILURE_TTL: Duration = Duration::from_secs(300);
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The number of bullet points of a version's changelog entry previewed.
+const PREVIEW_BULLETS: usize = 3;
+
+/// The GKE release notes changelog, raw markdown fetched from
+/// `GKE_CHANGELOG_URL` with a heading per version.
+#[derive(Clone, Debug)]
+pub struct Changelog {
+    client: reqwest::Client,
+
+    /// The last fetched changelog, locked while fetching so concurrent
+    /// previews wait for the same fetch.
+    cached: Arc<Mutex<Option<Cached>>>,
+}
+
+#[derive(Debug)]
+struct Cached {
+    url: String,
+
+    /// The markdown, or `None` when fetching it failed.
+    markdown: Option<String>,
+    fetched: Instant,
+}
+
+impl Changelog {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client, cached: Arc::default() }
+    }
+
+    /// The first bullet points of the changelog entry for `version`, empty
+    /// when the changelog has no entry for it or fetching it failed within
+    /// the last `FAILURE_TTL`.
+    pub async fn preview(&self, url: &str, version: &str) -> Result<Vec<String>, AppError> {
+        let markdown = self.fetch(url).await?;
+        Ok(preview(&markdown, version))
+    }
+
+    async fn fetch(&self, url: &str) -> Result<String, AppError> {
+        let mut cached = self.cached.lock().await;
+        if let Some(cached) = &*cached {
+            let ttl = if cached.markdown.is_some() { CACHE_TTL } else { FAILURE_TTL };
+            if cached.url == url && cached.fetched.elapsed() < ttl {
+                return Ok(cached.markdown.clone().unwrap_or_default());
+            }
+        }
+
+        let result = self.get(url).await;
+        let markdown = result.as_ref().ok().cloned();
+        *cached = Some(Cached { url: url.to_string(), markdown, fetched: Instant::now() });
+        result
+    }
+
+    async fn get(&self, url: &str) -> Result<String, AppError> {
+        let resp = self.client.get(url).timeout(TIMEOUT).send().await?;
+        let status = resp.status();
+        let text = resp.text().await?;
+        if !status.is_success() {
+            return Err(AppError::HttpGetFailed { status: status.as_u16(), body: text });
+        }
+        Ok(text)
+    }
+}
+
+/// The bullet points following the first heading mentioning `version`, up
+/// to the next heading.
+fn preview(markdown: &str, version: &str) -> Vec<String> {
+    let is_entry = |line: &str| {
+        line.starts_with('#')
+            && line.split_whitespace().any(|word| {
+                word.trim_matches(|c: char| !c.is_alphanumeric() && c != '.' && c != '-') == version
+            })
+    };
+
+    markdown
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| !is_entry(line))
+        .skip(1)
+        .take_while(|line| !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")))
+        .map(str::trim)
+        .filter(|bullet| !bullet.is_empty())
+        .take(PREVIEW_BULLETS)
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const CHANGELOG: &str = "\
+# GKE release notes
+
+## 1.28.3-gke.1200
+
+- Fixed a kubelet memory leak.
+- Updated containerd to 1.7.7.
+* Added support for the `n4` machine series.
+- Removed the deprecated `beta` APIs.
+
+## 1.28.3-gke.12
+
+- Initial release.
+
+## `1.27.7-gke.1121`
+
+Some prose which isn't previewed.
+
+- Patched CVE-2023-5528.
+";
+
+    #[test]
+    fn preview() {
+        assert_eq!(
+            super::preview(CHANGELOG, "1.28.3-gke.1200"),
+            [
+                "Fixed a kubelet memory leak.",
+                "Updated containerd to 1.7.7.",
+                "Added support for the `n4` machine series.",
+            ]
+        );
+        assert_eq!(super::preview(CHANGELOG, "1.28.3-gke.12"), ["Initial release."]);
+        assert_eq!(super::preview(CHANGELOG, "1.27.7-gke.1121"), ["Patched CVE-2023-5528."]);
+        assert!(super::preview(CHANGELOG, "1.29.0-gke.100").is_empty());
+        assert!(super::preview("", "1.28.3-gke.1200").is_empty());
+    }
+
+    #[tokio::test]
+    async fn fetch() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(CHANGELOG))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let changelog = Changelog::new(reqwest::Client::new());
+        for _ in 0..2 {
+            let preview = changelog.preview(&server.uri(), "1.28.3-gke.12").await.unwrap();
+            assert_eq!(preview, ["Initial release."]);
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+        match changelog.preview(&server.uri(), "1.28.3-gke.12").await {
+            Err(AppError::HttpGetFailed { status: 404, .. }) => {}
+            res => panic!("expected AppError::HttpGetFailed, got {res:?}"),
+        }
+        let preview = changelog.preview(&server.uri(), "1.28.3-gke.12").await.unwrap();
+        assert!(preview.is_empty(), "failures are cached");
+    }
+
+    #[tokio::test]
+    async fn concurrent_fetch() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(CHANGELOG)
+                    .set_delay(Duration::from_millis(100)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let changelog = Changelog::new(reqwest::Client::new());
+        let url = server.uri();
+        let previews = (0..3).map(|_| changelog.preview(&url, "1.28.3-gke.12"));
+        for preview in futures::future::join_all(previews).await {
+            assert_eq!(preview.unwrap(), ["Initial release."]);
+        }
+    }
+}
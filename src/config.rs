@@ -0,0 +1,744 @@
+use std::{collections::BTreeMap, env, str::FromStr, time::Duration};
+
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::Level;
+
+use crate::error::AppError;
+use crate::filter::{LocationFilter, RegexFilter};
+use crate::locale::Locale;
+use crate::message::attributes::payload::Payload;
+use crate::message::template::TemplateEngine;
+use crate::pubsub::resource_path;
+
+/// Settings used while processing messages. These are read from environment
+/// variables at startup and re-read when the process receives SIGHUP.
+///
+/// Settings which only take effect at startup (logging, listen address and
+/// Pub/Sub subscription) are read directly in `main`.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// `GCP_PROJECT`, used in place of the project number.
+    pub gcp_project: Option<String>,
+
+    /// `API_TOKEN`, when set POST requests require a matching
+    /// `Authorization: Bearer <token>` header.
+    pub api_token: Option<String>,
+
+    /// `SLACK_WEBHOOK`, the comma separated Incoming Webhook URLs messages
+    /// are posted to.
+    pub slack_webhooks: Vec<String>,
+
+    /// `SLACK_BOT_TOKEN` and `SLACK_CHANNEL`, when both are set messages are
+    /// posted with `chat.postMessage` and grouped into a thread per cluster.
+    pub slack_bot_token: Option<String>,
+    pub slack_channel: Option<String>,
+
+    /// `SLACK_API_URL`, the base URL of the Slack Web API.
+    pub slack_api_url: String,
+
+    /// `SLACK_SIGNING_SECRET`, when set `UpgradeAvailableEvent` messages
+    /// include an "Approve Upgrade" button, see `interaction`.
+    pub slack_signing_secret: Option<String>,
+
+    /// `GKE_API_URL`, the base URL of the GKE API upgrades are approved
+    /// through.
+    pub gke_api_url: String,
+
+    /// `SLACK_THREAD_TTL_SECS`, how long new messages are posted in the
+    /// thread of an earlier message for the same cluster.
+    pub slack_thread_ttl: Duration,
+
+    /// `DD_API_KEY`, when set all messages are also posted to the Datadog
+    /// Events API.
+    pub datadog_api_key: Option<String>,
+
+    /// `DD_SITE` (e.g. `datadoghq.eu`), the Datadog site events are posted
+    /// to as an API URL.
+    pub datadog_api_url: String,
+
+    /// `DD_ENV`, added to Datadog events as the `env` tag.
+    pub datadog_env: Option<String>,
+
+    /// `SMTP_HOST`, when set all messages are also sent as HTML emails, see
+    /// `SmtpConfig`.
+    pub smtp: Option<SmtpConfig>,
+
+    /// `UPDATE_SECURITY_MD`, when true security bulletins are proposed as
+    /// pull requests updating `SECURITY.md`, see `GitHubConfig`.
+    pub github: Option<GitHubConfig>,
+
+    /// `BUSINESS_HOURS_START` and `BUSINESS_HOURS_END` (e.g. `09:00` and
+    /// `17:00` UTC), both are required to configure business hours.
+    pub business_hours: Option<BusinessHours>,
+
+    /// `SLACK_OFF_HOURS_CHANNEL`, the channel `UpgradeEvent` messages are
+    /// posted to outside of business hours (requires `SLACK_BOT_TOKEN`).
+    pub slack_off_hours_channel: Option<String>,
+
+    /// `MAINTENANCE_EXCLUSION_CLUSTERS`, `UpgradeEvent` messages during an
+    /// exclusion are logged as warnings and flagged in Slack.
+    pub maintenance_exclusions: Vec<MaintenanceExclusion>,
+
+    /// `SLACK_TEXT_TEMPLATE_SECURITY`, `SLACK_TEXT_TEMPLATE_UPGRADE` and
+    /// `SLACK_TEXT_TEMPLATE_UPGRADE_AVAILABLE`, see `TemplateEngine`.
+    pub slack_text_templates: TemplateEngine,
+
+    /// `NOTIFICATION_LOCALE`, the language of the Slack text and log entries.
+    pub notification_locale: Locale,
+
+    /// `AUTO_UPGRADE_CLUSTERS`, a comma separated list of the clusters with
+    /// auto-upgrade enabled. When set, other clusters are assumed to have it
+    /// disabled.
+    pub auto_upgrade_clusters: Option<Vec<String>>,
+
+    /// `CLUSTER_LOCATION_FILTER`, messages for clusters in other locations
+    /// are dropped.
+    pub cluster_location_filter: LocationFilter,
+
+    /// `CLUSTER_NAME_REGEX`, messages for clusters with names it doesn't
+    /// match are dropped.
+    pub cluster_name_regex: RegexFilter,
+
+    /// `CURRENT_K8S_VERSION`, `UpgradeAvailableEvent`s for versions which
+    /// aren't newer are suppressed.
+    pub current_k8s_version: Option<String>,
+
+    /// `SLACK_SHOW_UPGRADE_BUTTON`, see `WebhookMessage::blocks`.
+    pub slack_show_upgrade_button: Option<bool>,
+
+    /// `GKE_CHANGELOG_URL`, required when `SHOW_CHANGELOG_PREVIEW` is true.
+    /// `UpgradeAvailableEvent` messages then preview the changelog entry of
+    /// the version, see `Changelog`.
+    pub changelog_url: Option<String>,
+
+    /// `SLACK_USE_ATTACHMENTS`, when true messages are color-coded by event
+    /// type, see `WebhookMessage::color`.
+    pub slack_use_attachments: bool,
+
+    /// `AVG_PODS_PER_NODE`, used to estimate the pods evicted by node pool
+    /// `UpgradeEvent`s.
+    pub avg_pods_per_node: u32,
+
+    /// `BULLETIN_AUTO_UPGRADE_DAYS`, the estimated days from the issue of a
+    /// security bulletin until clusters are auto-upgraded to a patched version.
+    pub bulletin_auto_upgrade_days: u32,
+
+    /// `SUPPRESS_NODE_POOL_UPGRADE_AVAILABLE`, whether node pool
+    /// `UpgradeAvailableEvent` messages are kept out of Slack.
+    pub suppress_node_pool_upgrade_available: bool,
+
+    /// `STALENESS_LIMIT_SECONDS`, messages published longer ago than this are
+    /// logged as stale, e.g. when working through a subscription backlog.
+    pub staleness_limit: Duration,
+
+    /// `SKIP_STALE_NOTIFICATIONS`, when true stale messages aren't posted to
+    /// Slack.
+    pub skip_stale_notifications: bool,
+
+    /// `BATCH_WINDOW_MS`, how long Slack messages are collected into a
+    /// batch after the first one, zero (the default) disables batching.
+    /// Only read at startup.
+    pub batch_window: Duration,
+
+    /// `BATCH_MAX_SIZE`, the number of messages after which a batch is
+    /// posted without waiting for the window to pass. Only read at startup.
+    pub batch_max_size: usize,
+
+    /// `LOG_LEVEL_SECURITY_BULLETIN`, `LOG_LEVEL_UPGRADE_AVAILABLE`,
+    /// `LOG_LEVEL_UPGRADE_EVENT` and `LOG_LEVEL_UNKNOWN`.
+    pub log_levels: LogLevelConfig,
+
+    /// `READINESS_SKIP_SLACK_CHECK`, whether `GET /readyz` succeeds before
+    /// a message has been posted to Slack (the default).
+    pub readiness_skip_slack_check: bool,
+
+    /// `DRY_RUN`, whether messages are only logged instead of being posted
+    /// to Slack, Datadog, email and GitHub.
+    pub dry_run: bool,
+
+    /// `DEDUP_TTL_SECS`, only read at startup.
+    pub dedup_ttl: Duration,
+
+    /// `RATE_LIMIT_RPS`, the requests per second allowed to `POST /` from
+    /// a single IP address (0 disables the limit), only read at startup.
+    pub rate_limit_rps: u32,
+
+    /// `MAX_BODY_BYTES`, larger `POST /` requests are rejected with HTTP 413
+    /// (0 disables the limit), only read at startup.
+    pub max_body_bytes: usize,
+
+    /// `SEVERITY_TREND_WINDOW`, the number of security bulletins the
+    /// severity of a new bulletin is compared to, only read at startup.
+    pub severity_trend_window: usize,
+
+    /// `HISTORY_DB_PATH`, the SQLite database notification attempts are
+    /// recorded in, disabled when unset. Only read at startup.
+    pub history_db_path: Option<String>,
+
+    /// `HISTORY_RETENTION_DAYS`, how long notification attempts are kept,
+    /// only read at startup.
+    pub history_retention: Duration,
+
+    /// `EVENT_HISTORY_SIZE`, the number of events kept for
+    /// `GET /api/v1/events`, only read at startup.
+    pub event_history_size: usize,
+
+    /// `PUBSUB_MAX_DELIVERY_ATTEMPTS`, how often a pulled message is
+    /// delivered before it's dead-lettered, see `PubSubPuller`.
+    pub pubsub_max_delivery_attempts: u32,
+
+    /// `PUBSUB_DEAD_LETTER_TOPIC`, the full path of the topic pulled messages
+    /// are published to after too many delivery attempts.
+    pub pubsub_dead_letter_topic: Option<String>,
+
+    /// `PUBSUB_EMULATOR_HOST`, when set messages are pulled from the emulator
+    /// without authentication.
+    pub pubsub_emulator_host: Option<String>,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, AppError> {
+        Ok(Self {
+            gcp_project: env::var("GCP_PROJECT").ok(),
+            api_token: env::var("API_TOKEN").ok(),
+            slack_webhooks: comma_separated(&env::var("SLACK_WEBHOOK").unwrap_or_default()),
+            slack_bot_token: env::var("SLACK_BOT_TOKEN").ok(),
+            slack_channel: env::var("SLACK_CHANNEL").ok(),
+            slack_api_url: env_or_default("SLACK_API_URL", "https://slack.com/api")
+                .map_err(|_| invalid("SLACK_API_URL should be a URL"))?,
+            slack_signing_secret: env::var("SLACK_SIGNING_SECRET").ok(),
+            gke_api_url: env_or_default("GKE_API_URL", "https://container.googleapis.com")
+                .map_err(|_| invalid("GKE_API_URL should be a URL"))?,
+            slack_thread_ttl: Duration::from_secs(
+                env_or_default("SLACK_THREAD_TTL_SECS", "86400")
+                    .map_err(|_| invalid("SLACK_THREAD_TTL_SECS should be a number"))?,
+            ),
+            datadog_api_key: env::var("DD_API_KEY").ok(),
+            datadog_api_url: format!(
+                "https://api.{}",
+                env::var("DD_SITE").unwrap_or_else(|_| "datadoghq.com".to_string())
+            ),
+            datadog_env: env::var("DD_ENV").ok(),
+            smtp: match env::var("SMTP_HOST") {
+                Ok(host) => Some(SmtpConfig {
+                    host,
+                    port: env_or_default("SMTP_PORT", "587")
+                        .map_err(|_| invalid("SMTP_PORT should be a port number"))?,
+                    username: env::var("SMTP_USERNAME").ok(),
+                    password: env::var("SMTP_PASSWORD").ok(),
+                    from: env::var("SMTP_FROM")
+                        .map_err(|_| invalid("SMTP_FROM is required when SMTP_HOST is set"))?,
+                    to: env::var("SMTP_TO")
+                        .map_err(|_| invalid("SMTP_TO is required when SMTP_HOST is set"))?,
+                }),
+                Err(_) => None,
+            },
+            github: match env_or_default("UPDATE_SECURITY_MD", "false")
+                .map_err(|_| invalid("UPDATE_SECURITY_MD should be true or false"))?
+            {
+                true => Some(GitHubConfig {
+                    api_url: env_or_default("GITHUB_API_URL", "https://api.github.com")
+                        .map_err(|_| invalid("GITHUB_API_URL should be a URL"))?,
+                    token: env::var("GITHUB_TOKEN")
+                        .map_err(|_| invalid("GITHUB_TOKEN is required by UPDATE_SECURITY_MD"))?,
+                    security_repo: env::var("GITHUB_SECURITY_REPO").map_err(|_| {
+                        invalid("GITHUB_SECURITY_REPO is required by UPDATE_SECURITY_MD")
+                    })?,
+                }),
+                false => None,
+            },
+            business_hours: match (
+                env_optional::<BusinessTime>("BUSINESS_HOURS_START")
+                    .map_err(|_| invalid("BUSINESS_HOURS_START should be formatted as HH:MM"))?,
+                env_optional::<BusinessTime>("BUSINESS_HOURS_END")
+                    .map_err(|_| invalid("BUSINESS_HOURS_END should be formatted as HH:MM"))?,
+            ) {
+                (Some(BusinessTime(start)), Some(BusinessTime(end))) => {
+                    Some(BusinessHours { start, end })
+                }
+                _ => None,
+            },
+            slack_off_hours_channel: env::var("SLACK_OFF_HOURS_CHANNEL").ok(),
+            maintenance_exclusions: env::var("MAINTENANCE_EXCLUSION_CLUSTERS")
+                .map_or(Ok(vec![]), |exclusions| serde_json::from_str(&exclusions))
+                .map_err(|_| {
+                    invalid(
+                        "MAINTENANCE_EXCLUSION_CLUSTERS should be a JSON array of \
+                         {cluster_name, start, end}",
+                    )
+                })?,
+            slack_text_templates: TemplateEngine::new(
+                env::var("SLACK_TEXT_TEMPLATE_SECURITY").ok(),
+                env::var("SLACK_TEXT_TEMPLATE_UPGRADE").ok(),
+                env::var("SLACK_TEXT_TEMPLATE_UPGRADE_AVAILABLE").ok(),
+            )?,
+            notification_locale: env_or_default("NOTIFICATION_LOCALE", "en")?,
+            auto_upgrade_clusters: env::var("AUTO_UPGRADE_CLUSTERS")
+                .ok()
+                .map(|clusters| comma_separated(&clusters)),
+            cluster_location_filter: LocationFilter::new(
+                &env::var("CLUSTER_LOCATION_FILTER").unwrap_or_default(),
+            ),
+            cluster_name_regex: RegexFilter::new(env::var("CLUSTER_NAME_REGEX").ok().as_deref())
+                .map_err(|err| invalid(&format!("CLUSTER_NAME_REGEX is invalid: {err}")))?,
+            current_k8s_version: env::var("CURRENT_K8S_VERSION").ok(),
+            slack_show_upgrade_button: env_optional("SLACK_SHOW_UPGRADE_BUTTON")
+                .map_err(|_| invalid("SLACK_SHOW_UPGRADE_BUTTON should be true or false"))?,
+            changelog_url: match env_or_default("SHOW_CHANGELOG_PREVIEW", "false")
+                .map_err(|_| invalid("SHOW_CHANGELOG_PREVIEW should be true or false"))?
+            {
+                true => Some(env::var("GKE_CHANGELOG_URL").map_err(|_| {
+                    invalid("GKE_CHANGELOG_URL is required by SHOW_CHANGELOG_PREVIEW")
+                })?),
+                false => None,
+            },
+            slack_use_attachments: env_or_default("SLACK_USE_ATTACHMENTS", "false")
+                .map_err(|_| invalid("SLACK_USE_ATTACHMENTS should be true or false"))?,
+            avg_pods_per_node: env_or_default("AVG_PODS_PER_NODE", "10")
+                .map_err(|_| invalid("AVG_PODS_PER_NODE should be a number"))?,
+            bulletin_auto_upgrade_days: env_or_default("BULLETIN_AUTO_UPGRADE_DAYS", "30")
+                .map_err(|_| invalid("BULLETIN_AUTO_UPGRADE_DAYS should be a number"))?,
+            suppress_node_pool_upgrade_available: env_or_default(
+                "SUPPRESS_NODE_POOL_UPGRADE_AVAILABLE",
+                "true",
+            )
+            .map_err(|_| invalid("SUPPRESS_NODE_POOL_UPGRADE_AVAILABLE should be true or false"))?,
+            staleness_limit: Duration::from_secs(
+                env_or_default("STALENESS_LIMIT_SECONDS", "3600")
+                    .map_err(|_| invalid("STALENESS_LIMIT_SECONDS should be a number"))?,
+            ),
+            skip_stale_notifications: env_or_default("SKIP_STALE_NOTIFICATIONS", "false")
+                .map_err(|_| invalid("SKIP_STALE_NOTIFICATIONS should be true or false"))?,
+            batch_window: Duration::from_millis(
+                env_or_default("BATCH_WINDOW_MS", "0")
+                    .map_err(|_| invalid("BATCH_WINDOW_MS should be a number"))?,
+            ),
+            batch_max_size: env_or_default("BATCH_MAX_SIZE", "10")
+                .map_err(|_| invalid("BATCH_MAX_SIZE should be a number"))?,
+            log_levels: LogLevelConfig {
+                security_bulletin: env_or_default("LOG_LEVEL_SECURITY_BULLETIN", "info")
+                    .map_err(|_| invalid("LOG_LEVEL_SECURITY_BULLETIN should be a log level"))?,
+                upgrade_available: env_or_default("LOG_LEVEL_UPGRADE_AVAILABLE", "info")
+                    .map_err(|_| invalid("LOG_LEVEL_UPGRADE_AVAILABLE should be a log level"))?,
+                upgrade_event: env_or_default("LOG_LEVEL_UPGRADE_EVENT", "info")
+                    .map_err(|_| invalid("LOG_LEVEL_UPGRADE_EVENT should be a log level"))?,
+                unknown: env_or_default("LOG_LEVEL_UNKNOWN", "info")
+                    .map_err(|_| invalid("LOG_LEVEL_UNKNOWN should be a log level"))?,
+            },
+            readiness_skip_slack_check: env_or_default("READINESS_SKIP_SLACK_CHECK", "true")
+                .map_err(|_| invalid("READINESS_SKIP_SLACK_CHECK should be true or false"))?,
+            dry_run: env_or_default("DRY_RUN", "false")
+                .map_err(|_| invalid("DRY_RUN should be true or false"))?,
+            dedup_ttl: Duration::from_secs(
+                env_or_default("DEDUP_TTL_SECS", "300")
+                    .map_err(|_| invalid("DEDUP_TTL_SECS should be a number"))?,
+            ),
+            rate_limit_rps: env_or_default("RATE_LIMIT_RPS", "0")
+                .map_err(|_| invalid("RATE_LIMIT_RPS should be a number"))?,
+            max_body_bytes: env_or_default("MAX_BODY_BYTES", "1048576")
+                .map_err(|_| invalid("MAX_BODY_BYTES should be a number"))?,
+            severity_trend_window: env_or_default("SEVERITY_TREND_WINDOW", "5")
+                .map_err(|_| invalid("SEVERITY_TREND_WINDOW should be a number"))?,
+            history_db_path: env::var("HISTORY_DB_PATH").ok(),
+            history_retention: Duration::from_secs(
+                env_or_default::<u64>("HISTORY_RETENTION_DAYS", "30")
+                    .map_err(|_| invalid("HISTORY_RETENTION_DAYS should be a number"))?
+                    * 24
+                    * 60
+                    * 60,
+            ),
+            event_history_size: env_or_default("EVENT_HISTORY_SIZE", "100")
+                .map_err(|_| invalid("EVENT_HISTORY_SIZE should be a number"))?,
+            pubsub_max_delivery_attempts: env_or_default("PUBSUB_MAX_DELIVERY_ATTEMPTS", "5")
+                .map_err(|_| invalid("PUBSUB_MAX_DELIVERY_ATTEMPTS should be a number"))?,
+            pubsub_dead_letter_topic: env::var("PUBSUB_DEAD_LETTER_TOPIC")
+                .ok()
+                .map(|topic_id| resource_path("topics", topic_id))
+                .transpose()?,
+            pubsub_emulator_host: env::var("PUBSUB_EMULATOR_HOST").ok(),
+        })
+    }
+}
+
+/// The levels processed messages are logged at, per event type. Invalid
+/// messages are always logged as errors.
+#[derive(Clone, Copy, Debug)]
+pub struct LogLevelConfig {
+    pub security_bulletin: Level,
+    pub upgrade_available: Level,
+
+    /// Used for `UpgradeEvent` and `NodeUpgradeCompleteEvent` messages.
+    pub upgrade_event: Level,
+    pub unknown: Level,
+}
+
+impl Default for LogLevelConfig {
+    fn default() -> Self {
+        Self {
+            security_bulletin: Level::INFO,
+            upgrade_available: Level::INFO,
+            upgrade_event: Level::INFO,
+            unknown: Level::INFO,
+        }
+    }
+}
+
+impl LogLevelConfig {
+    /// The level for messages with the payload. `ClusterStatusChangeEvent`
+    /// and node pool configuration, autoscaling, creation and deletion
+    /// messages are always logged at INFO.
+    pub fn level(&self, payload: &Payload) -> Level {
+        match payload {
+            Payload::SecurityBulletinEvent(_) => self.security_bulletin,
+            Payload::UpgradeAvailableEvent(_) => self.upgrade_available,
+            Payload::UpgradeEvent(_) | Payload::NodeUpgradeCompleteEvent(_) => self.upgrade_event,
+            Payload::ClusterStatusChangeEvent(_)
+            | Payload::NodePoolConfigChangeEvent(_)
+            | Payload::NodePoolAutoscalingEvent(_)
+            | Payload::NodePoolCreatedEvent(_)
+            | Payload::NodePoolDeletedEvent(_) => Level::INFO,
+            Payload::UnknownType(_) | Payload::None => self.unknown,
+        }
+    }
+}
+
+/// The SMTP server emails are sent through with STARTTLS, and their sender
+/// and recipients.
+#[derive(Clone)]
+pub struct SmtpConfig {
+    /// `SMTP_HOST` and `SMTP_PORT` (default 587).
+    pub host: String,
+    pub port: u16,
+
+    /// `SMTP_USERNAME` and `SMTP_PASSWORD`, both are required to
+    /// authenticate.
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    /// `SMTP_FROM`, the sender address.
+    pub from: String,
+
+    /// `SMTP_TO`, a comma separated list of recipient addresses.
+    pub to: String,
+}
+
+/// Leaves out the password, so it isn't logged along with the configuration.
+impl std::fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .finish()
+    }
+}
+
+/// The GitHub repository whose `SECURITY.md` security bulletins are added
+/// to.
+#[derive(Clone, Debug)]
+pub struct GitHubConfig {
+    /// `GITHUB_API_URL`, the base URL of the GitHub REST API.
+    pub api_url: String,
+
+    /// `GITHUB_TOKEN`, which needs write access to contents and pull requests.
+    pub token: String,
+
+    /// `GITHUB_SECURITY_REPO`, the repository as `owner/repo`.
+    pub security_repo: String,
+}
+
+/// Business hours in UTC, from `start` (inclusive) to `end` (exclusive).
+/// When `end` is before `start` the hours span midnight.
+#[derive(Clone, Copy, Debug)]
+pub struct BusinessHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl BusinessHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// A period in which a cluster shouldn't be upgraded, from `start`
+/// (inclusive) to `end` (exclusive), e.g. a release freeze.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MaintenanceExclusion {
+    pub cluster_name: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl MaintenanceExclusion {
+    pub fn contains(&self, cluster_name: &str, time: DateTime<Utc>) -> bool {
+        self.cluster_name == cluster_name && self.start <= time && time < self.end
+    }
+}
+
+struct BusinessTime(NaiveTime);
+
+impl FromStr for BusinessTime {
+    type Err = chrono::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NaiveTime::parse_from_str(s, "%H:%M").map(BusinessTime)
+    }
+}
+
+/// The active configuration as returned by `GET /api/v1/config`, for
+/// verifying which settings are in effect. Webhook URLs, tokens, keys and
+/// passwords are only shown as `set` or `unset`.
+#[derive(Debug, Serialize)]
+pub struct ConfigView {
+    log_levels: BTreeMap<&'static str, String>,
+    gcp_project: Option<String>,
+    api_token: &'static str,
+    slack_webhook: &'static str,
+    slack_bot_token: &'static str,
+    slack_channel: Option<String>,
+    slack_off_hours_channel: Option<String>,
+    slack_api_url: String,
+    slack_signing_secret: &'static str,
+    slack_thread_ttl: String,
+    slack_show_upgrade_button: Option<bool>,
+    slack_use_attachments: bool,
+    gke_api_url: String,
+    datadog_api_key: &'static str,
+    datadog_api_url: String,
+    datadog_env: Option<String>,
+    smtp_host: Option<String>,
+    smtp_password: &'static str,
+    smtp_to: Option<String>,
+    github_security_repo: Option<String>,
+    github_token: &'static str,
+    business_hours: Option<String>,
+    maintenance_exclusions: Vec<MaintenanceExclusion>,
+    notification_locale: Locale,
+    auto_upgrade_clusters: Option<Vec<String>>,
+    cluster_location_filter: LocationFilter,
+    cluster_name_regex: RegexFilter,
+    current_k8s_version: Option<String>,
+    changelog_url: Option<String>,
+    avg_pods_per_node: u32,
+    bulletin_auto_upgrade_days: u32,
+    suppress_node_pool_upgrade_available: bool,
+    staleness_limit: String,
+    skip_stale_notifications: bool,
+    batch_window: String,
+    batch_max_size: usize,
+    readiness_skip_slack_check: bool,
+    dry_run: bool,
+    dedup_ttl: String,
+    rate_limit_rps: u32,
+    max_body_bytes: usize,
+    severity_trend_window: usize,
+    history_db_path: Option<String>,
+    history_retention: String,
+    event_history_size: usize,
+    pubsub_max_delivery_attempts: u32,
+    pubsub_dead_letter_topic: Option<String>,
+    pubsub_emulator_host: Option<String>,
+}
+
+impl From<&Config> for ConfigView {
+    fn from(config: &Config) -> Self {
+        fn set<T>(value: Option<T>) -> &'static str {
+            match value {
+                Some(_) => "set",
+                None => "unset",
+            }
+        }
+        let duration = |duration: Duration| humantime::format_duration(duration).to_string();
+        let smtp = config.smtp.as_ref();
+        let github = config.github.as_ref();
+        let levels = &config.log_levels;
+
+        ConfigView {
+            log_levels: BTreeMap::from([
+                ("security_bulletin", levels.security_bulletin.to_string()),
+                ("upgrade_available", levels.upgrade_available.to_string()),
+                ("upgrade_event", levels.upgrade_event.to_string()),
+                ("unknown", levels.unknown.to_string()),
+            ]),
+            gcp_project: config.gcp_project.clone(),
+            api_token: set(config.api_token.as_ref()),
+            slack_webhook: set(config.slack_webhooks.first()),
+            slack_bot_token: set(config.slack_bot_token.as_ref()),
+            slack_channel: config.slack_channel.clone(),
+            slack_off_hours_channel: config.slack_off_hours_channel.clone(),
+            slack_api_url: config.slack_api_url.clone(),
+            slack_signing_secret: set(config.slack_signing_secret.as_ref()),
+            slack_thread_ttl: duration(config.slack_thread_ttl),
+            slack_show_upgrade_button: config.slack_show_upgrade_button,
+            slack_use_attachments: config.slack_use_attachments,
+            gke_api_url: config.gke_api_url.clone(),
+            datadog_api_key: set(config.datadog_api_key.as_ref()),
+            datadog_api_url: config.datadog_api_url.clone(),
+            datadog_env: config.datadog_env.clone(),
+            smtp_host: smtp.map(|smtp| format!("{}:{}", smtp.host, smtp.port)),
+            smtp_password: set(smtp.and_then(|smtp| smtp.password.as_ref())),
+            smtp_to: smtp.map(|smtp| smtp.to.clone()),
+            github_security_repo: github.map(|github| github.security_repo.clone()),
+            github_token: set(github),
+            business_hours: config.business_hours.map(|hours| {
+                format!("{}-{}", hours.start.format("%H:%M"), hours.end.format("%H:%M"))
+            }),
+            maintenance_exclusions: config.maintenance_exclusions.clone(),
+            notification_locale: config.notification_locale,
+            auto_upgrade_clusters: config.auto_upgrade_clusters.clone(),
+            cluster_location_filter: config.cluster_location_filter.clone(),
+            cluster_name_regex: config.cluster_name_regex.clone(),
+            current_k8s_version: config.current_k8s_version.clone(),
+            changelog_url: config.changelog_url.clone(),
+            avg_pods_per_node: config.avg_pods_per_node,
+            bulletin_auto_upgrade_days: config.bulletin_auto_upgrade_days,
+            suppress_node_pool_upgrade_available: config.suppress_node_pool_upgrade_available,
+            staleness_limit: duration(config.staleness_limit),
+            skip_stale_notifications: config.skip_stale_notifications,
+            batch_window: duration(config.batch_window),
+            batch_max_size: config.batch_max_size,
+            readiness_skip_slack_check: config.readiness_skip_slack_check,
+            dry_run: config.dry_run,
+            dedup_ttl: duration(config.dedup_ttl),
+            rate_limit_rps: config.rate_limit_rps,
+            max_body_bytes: config.max_body_bytes,
+            severity_trend_window: config.severity_trend_window,
+            history_db_path: config.history_db_path.clone(),
+            history_retention: duration(config.history_retention),
+            event_history_size: config.event_history_size,
+            pubsub_max_delivery_attempts: config.pubsub_max_delivery_attempts,
+            pubsub_dead_letter_topic: config.pubsub_dead_letter_topic.clone(),
+            pubsub_emulator_host: config.pubsub_emulator_host.clone(),
+        }
+    }
+}
+
+fn invalid(message: &str) -> AppError {
+    AppError::InvalidConfig(message.to_string())
+}
+
+pub fn env_or_default<F: FromStr>(key: &str, default: &str) -> Result<F, F::Err> {
+    env::var(key).unwrap_or_else(|_| default.to_string()).parse()
+}
+
+fn env_optional<F: FromStr>(key: &str) -> Result<Option<F>, F::Err> {
+    env::var(key).ok().map(|value| value.parse()).transpose()
+}
+
+/// The non-empty, trimmed values of a comma separated list.
+pub fn comma_separated(list: &str) -> Vec<String> {
+    list.split(',').map(str::trim).filter(|value| !value.is_empty()).map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::attributes::payload::{
+        ClusterStatusChangeEvent, NodeUpgradeCompleteEvent, SecurityBulletinEvent,
+        UpgradeAvailableEvent, UpgradeEvent,
+    };
+
+    fn time(s: &str) -> NaiveTime {
+        s.parse::<BusinessTime>().unwrap().0
+    }
+
+    #[test]
+    fn comma_separated_list() {
+        assert_eq!(comma_separated(" a,b, c ,"), ["a", "b", "c"]);
+        assert!(comma_separated("").is_empty());
+    }
+
+    #[test]
+    fn smtp_debug() {
+        let smtp = SmtpConfig {
+            host: "smtp.example.com".to_string(),
+            port: 587,
+            username: Some("user".to_string()),
+            password: Some("hunter2".to_string()),
+            from: "gke@example.com".to_string(),
+            to: "ops@example.com".to_string(),
+        };
+
+        let debug = format!("{smtp:?}");
+        assert!(!debug.contains("hunter2"), "{debug}");
+        assert!(debug.contains(r#"password: Some("<redacted>")"#), "{debug}");
+        assert!(debug.contains(r#"username: Some("user")"#), "{debug}");
+    }
+
+    #[test]
+    fn business_hours() {
+        let hours = BusinessHours { start: time("09:00"), end: time("17:00") };
+
+        assert!(!hours.contains(time("08:59")));
+        assert!(hours.contains(time("09:00")));
+        assert!(hours.contains(time("16:59")));
+        assert!(!hours.contains(time("17:00")));
+    }
+
+    #[test]
+    fn business_hours_spanning_midnight() {
+        let hours = BusinessHours { start: time("22:00"), end: time("06:00") };
+
+        assert!(hours.contains(time("23:30")));
+        assert!(hours.contains(time("05:59")));
+        assert!(!hours.contains(time("06:00")));
+        assert!(!hours.contains(time("12:00")));
+    }
+
+    #[test]
+    fn maintenance_exclusion() {
+        let exclusions: Vec<MaintenanceExclusion> = serde_json::from_str(
+            r#"[{"cluster_name": "prod", "start": "2023-12-20T00:00:00Z", "end": "2024-01-02T00:00:00Z"}]"#,
+        )
+        .unwrap();
+        let exclusion = &exclusions[0];
+        let time = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+
+        assert!(!exclusion.contains("prod", time("2023-12-19T23:59:59Z")));
+        assert!(exclusion.contains("prod", time("2023-12-20T00:00:00Z")));
+        assert!(exclusion.contains("prod", time("2024-01-01T23:59:59Z")));
+        assert!(!exclusion.contains("prod", time("2024-01-02T00:00:00Z")));
+        assert!(!exclusion.contains("staging", time("2023-12-24T12:00:00Z")));
+    }
+
+    #[test]
+    fn log_levels() {
+        let log_levels = LogLevelConfig {
+            security_bulletin: "error".parse().unwrap(),
+            upgrade_available: "debug".parse().unwrap(),
+            upgrade_event: "warn".parse().unwrap(),
+            unknown: "trace".parse().unwrap(),
+        };
+
+        let cases = [
+            (Payload::SecurityBulletinEvent(SecurityBulletinEvent::default()), Level::ERROR),
+            (Payload::UpgradeAvailableEvent(UpgradeAvailableEvent::default()), Level::DEBUG),
+            (Payload::UpgradeEvent(UpgradeEvent::default()), Level::WARN),
+            (Payload::NodeUpgradeCompleteEvent(NodeUpgradeCompleteEvent::default()), Level::WARN),
+            (Payload::ClusterStatusChangeEvent(ClusterStatusChangeEvent::default()), Level::INFO),
+            (Payload::UnknownType("{}".to_string()), Level::TRACE),
+        ];
+        for (payload, expected) in cases {
+            assert_eq!(log_levels.level(&payload), expected, "{payload:?}");
+        }
+
+        assert!("verbose".parse::<Level>().is_err());
+    }
+
+    #[test]
+    fn business_time() {
+        assert!("9:00".parse::<BusinessTime>().is_ok());
+        assert!("17:30".parse::<BusinessTime>().is_ok());
+        assert!("25:00".parse::<BusinessTime>().is_err());
+        assert!("nine".parse::<BusinessTime>().is_err());
+    }
+}
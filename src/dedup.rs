@@ -0,0 +1,139 @@
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+
+const CAPACITY: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
+
+/// Remembers recently processed Pub/Sub `message_id`s so that redelivered
+/// messages (Pub/Sub guarantees at-least-once delivery) are only processed
+/// once within the configured TTL.
+#[derive(Clone, Debug)]
+pub struct DeduplicationCache {
+    seen: Arc<Mutex<LruCache<String, Seen>>>,
+    ttl: Duration,
+}
+
+#[derive(Debug)]
+struct Seen {
+    received: Instant,
+
+    /// The backends notified by an earlier attempt which failed, when the
+    /// message should be processed again.
+    retry: Option<Vec<&'static str>>,
+}
+
+impl Seen {
+    fn new(retry: Option<Vec<&'static str>>) -> Self {
+        Seen { received: Instant::now(), retry }
+    }
+}
+
+impl DeduplicationCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { seen: Arc::new(Mutex::new(LruCache::new(CAPACITY))), ttl }
+    }
+
+    /// Returns true when `message_id` was already seen within the TTL and
+    /// isn't marked for a retry, otherwise records it and returns false.
+    pub fn is_duplicate(&self, message_id: &str) -> bool {
+        let mut seen = self.seen.lock().unwrap();
+        if let Some(entry) = seen.peek(message_id) {
+            if entry.retry.is_some() {
+                return false;
+            }
+            if entry.received.elapsed() < self.ttl {
+                return true;
+            }
+        }
+        seen.put(message_id.to_string(), Seen::new(None));
+        false
+    }
+
+    /// Marks `message_id` for a retry, so that a redelivery of a message which
+    /// failed to be processed isn't skipped, while the backends in `notified`
+    /// aren't notified again. The mark is kept regardless of the TTL, until
+    /// pruned.
+    pub fn retry(&self, message_id: &str, notified: Vec<&'static str>) {
+        self.seen.lock().unwrap().put(message_id.to_string(), Seen::new(Some(notified)));
+    }
+
+    /// The backends notified by an earlier attempt to process `message_id`,
+    /// clearing its retry mark.
+    pub fn take_notified(&self, message_id: &str) -> Vec<&'static str> {
+        let mut seen = self.seen.lock().unwrap();
+        seen.peek_mut(message_id).and_then(|entry| entry.retry.take()).unwrap_or_default()
+    }
+
+    /// Removes all entries older than the TTL.
+    pub fn prune(&self) {
+        let mut seen = self.seen.lock().unwrap();
+        while seen.peek_lru().is_some_and(|(_, entry)| entry.received.elapsed() >= self.ttl) {
+            seen.pop_lru();
+        }
+    }
+
+    /// Spawns a background task pruning expired entries every `interval`.
+    pub fn spawn_pruner(&self, interval: Duration) {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                cache.prune();
+            }
+        });
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.seen.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate() {
+        let cache = DeduplicationCache::new(Duration::from_secs(300));
+
+        assert!(!cache.is_duplicate("1722065266338564"));
+        assert!(cache.is_duplicate("1722065266338564"));
+        assert!(!cache.is_duplicate("5998008844325583"));
+    }
+
+    #[test]
+    fn retry() {
+        let cache = DeduplicationCache::new(Duration::from_secs(300));
+        assert!(!cache.is_duplicate("1722065266338564"));
+        assert!(cache.take_notified("1722065266338564").is_empty());
+
+        cache.retry("1722065266338564", vec!["datadog"]);
+        assert!(!cache.is_duplicate("1722065266338564"));
+        assert_eq!(cache.take_notified("1722065266338564"), ["datadog"]);
+        assert!(cache.is_duplicate("1722065266338564"));
+    }
+
+    #[test]
+    fn expired() {
+        let cache = DeduplicationCache::new(Duration::ZERO);
+
+        assert!(!cache.is_duplicate("1722065266338564"));
+        assert!(!cache.is_duplicate("1722065266338564"));
+    }
+
+    #[test]
+    fn prune() {
+        let cache = DeduplicationCache::new(Duration::from_millis(50));
+        cache.is_duplicate("1722065266338564");
+        std::thread::sleep(Duration::from_millis(60));
+        cache.is_duplicate("5998008844325583");
+
+        cache.prune();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.is_duplicate("5998008844325583"));
+    }
+}
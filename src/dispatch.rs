@@ -0,0 +1,86 @@
+use futures::future::join_all;
+
+use crate::error::AppError;
+use crate::message::notifier::{Notifier, NotifierConfig};
+
+/// The outcome of posting a message to one destination.
+#[derive(Debug)]
+pub struct NotificationResult {
+    /// The name of the destination, e.g. of the webhook in the
+    /// `WebhookRegistry`.
+    pub destination: String,
+    pub result: Result<String, AppError>,
+}
+
+/// Posts the message to every named destination concurrently, so a slow or
+/// failing destination doesn't hold up the others. The results are in the
+/// order of `destinations`.
+pub async fn dispatch_all_notifiers(
+    client: &reqwest::Client,
+    destinations: &[(String, NotifierConfig)],
+    notifier: &dyn Notifier,
+) -> Vec<NotificationResult> {
+    join_all(destinations.iter().map(|(name, config)| async move {
+        NotificationResult {
+            destination: name.clone(),
+            result: notifier.post(client, config).await,
+        }
+    }))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::message::slack::{Annotations, WebhookMessage};
+    use crate::message::tests::test_messages;
+    use crate::webhooks::WebhookRegistry;
+    use serde_json::json;
+    use std::time::{Duration, Instant};
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn dispatch_all() {
+        let test = test_messages().into_iter().find(|test| !test.message.is_invalid()).unwrap();
+        let message =
+            WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+
+        let mut servers = vec![];
+        for status in [500, 200, 200] {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .respond_with(
+                    ResponseTemplate::new(status)
+                        .set_body_string("ok")
+                        .set_delay(Duration::from_millis(300)),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+            servers.push(server);
+        }
+        let registry = WebhookRegistry::from_json(
+            &json!({
+                "a-failing": { "url": servers[0].uri() },
+                "b-slack": { "url": servers[1].uri() },
+                "c-alerts": { "url": servers[2].uri() },
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let started = Instant::now();
+        let results =
+            dispatch_all_notifiers(&reqwest::Client::new(), registry.destinations(), &message)
+                .await;
+        assert!(started.elapsed() < Duration::from_millis(800), "{:?}", started.elapsed());
+
+        let destinations: Vec<_> = results.iter().map(|r| r.destination.as_str()).collect();
+        assert_eq!(destinations, ["a-failing", "b-slack", "c-alerts"]);
+        assert!(matches!(results[0].result, Err(AppError::HttpPostFailed { status: 500, .. })));
+        assert_eq!(results[1].result.as_ref().unwrap(), "ok");
+        assert_eq!(results[2].result.as_ref().unwrap(), "ok");
+    }
+}
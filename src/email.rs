@@ -0,0 +1,83 @@
+use lettre::message::{header::ContentType, Mailboxes};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+use crate::config::SmtpConfig;
+use crate::error::AppError;
+
+/// Sends an HTML email to `to`, a comma separated list of addresses, through
+/// the SMTP server in `smtp` using STARTTLS.
+pub async fn send_email(
+    smtp: &SmtpConfig,
+    to: &str,
+    subject: &str,
+    body: &str,
+) -> Result<(), AppError> {
+    let invalid_to = |err: String| {
+        AppError::InvalidConfig(format!(
+            "invalid recipients `{to}`, expected email addresses: {err}"
+        ))
+    };
+
+    let from = smtp.from.parse().map_err(|err| {
+        AppError::InvalidConfig(format!("SMTP_FROM should be an email address: {err}"))
+    })?;
+    let mut email =
+        lettre::Message::builder().from(from).subject(subject).header(ContentType::TEXT_HTML);
+    for mailbox in to.parse::<Mailboxes>().map_err(|err| invalid_to(err.to_string()))? {
+        email = email.to(mailbox);
+    }
+    let email = email.body(body.to_string()).map_err(|err| invalid_to(err.to_string()))?;
+
+    let mut transport =
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?.port(smtp.port);
+    if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    transport.build().send(email).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smtp() -> SmtpConfig {
+        SmtpConfig {
+            host: "localhost".to_string(),
+            // Nothing listens on port 1, so connecting fails right away
+            port: 1,
+            username: None,
+            password: None,
+            from: "gke@example.com".to_string(),
+            to: "ops@example.com".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_addresses() {
+        let invalid_from = SmtpConfig { from: "not an address".to_string(), ..smtp() };
+        match send_email(&invalid_from, &invalid_from.to, "subject", "<p>body</p>").await {
+            Err(AppError::InvalidConfig(err)) => assert!(err.contains("SMTP_FROM"), "{err}"),
+            res => panic!("expected AppError::InvalidConfig, got {res:?}"),
+        }
+
+        for to in ["not an address", ""] {
+            match send_email(&smtp(), to, "subject", "<p>body</p>").await {
+                Err(AppError::InvalidConfig(err)) => assert!(err.contains("invalid recipients")),
+                res => panic!("expected AppError::InvalidConfig for `{to}`, got {res:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_server() {
+        let smtp = smtp();
+        let to = "ops@example.com, security@example.com";
+        match send_email(&smtp, to, "subject", "<p>body</p>").await {
+            Err(AppError::SmtpFailed(_)) => {}
+            res => panic!("expected AppError::SmtpFailed, got {res:?}"),
+        }
+    }
+}
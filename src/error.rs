@@ -0,0 +1,103 @@
+use std::fmt::Display;
+
+/// Errors returned while reading the configuration, formatting messages,
+/// calling the Slack, Pub/Sub and GitHub APIs, sending emails and recording
+/// the notification history.
+#[derive(Debug)]
+pub enum AppError {
+    /// The server responded to a POST, PUT or PATCH request with a
+    /// non-success status.
+    HttpPostFailed {
+        status: u16,
+        body: String,
+    },
+
+    /// The server responded to a GET request with a non-success status.
+    HttpGetFailed {
+        status: u16,
+        body: String,
+    },
+
+    /// The request couldn't be sent or its response couldn't be read.
+    RequestFailed(reqwest::Error),
+
+    /// The Slack Web API responded with `ok: false`.
+    SlackApiFailed(String),
+
+    /// The GitHub API returned an unexpected response.
+    GitHubApiFailed(String),
+
+    /// Sending an email through the SMTP server failed.
+    SmtpFailed(lettre::transport::smtp::Error),
+
+    /// Querying the notification history database failed.
+    DatabaseFailed(sqlx::Error),
+
+    /// Retrieving an access token for a Google API failed.
+    AuthFailed(gcp_auth::Error),
+
+    DeserializationFailed(serde_json::Error),
+
+    /// An environment variable has an invalid value.
+    InvalidConfig(String),
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::HttpPostFailed { status, body } => write!(f, "HTTP {status}: {body}"),
+            AppError::HttpGetFailed { status, body } => write!(f, "HTTP GET {status}: {body}"),
+            AppError::RequestFailed(err) => write!(f, "request failed: {err}"),
+            AppError::SlackApiFailed(err) => write!(f, "Slack API error: {err}"),
+            AppError::GitHubApiFailed(err) => write!(f, "GitHub API error: {err}"),
+            AppError::SmtpFailed(err) => write!(f, "sending email failed: {err}"),
+            AppError::DatabaseFailed(err) => write!(f, "database query failed: {err}"),
+            AppError::AuthFailed(err) => write!(f, "authentication failed: {err}"),
+            AppError::DeserializationFailed(err) => write!(f, "deserialization failed: {err}"),
+            AppError::InvalidConfig(err) => f.write_str(err),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::RequestFailed(err) => Some(err),
+            AppError::SmtpFailed(err) => Some(err),
+            AppError::DatabaseFailed(err) => Some(err),
+            AppError::AuthFailed(err) => Some(err),
+            AppError::DeserializationFailed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> Self {
+        AppError::RequestFailed(err)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::DeserializationFailed(err)
+    }
+}
+
+impl From<lettre::transport::smtp::Error> for AppError {
+    fn from(err: lettre::transport::smtp::Error) -> Self {
+        AppError::SmtpFailed(err)
+    }
+}
+
+impl From<gcp_auth::Error> for AppError {
+    fn from(err: gcp_auth::Error) -> Self {
+        AppError::AuthFailed(err)
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        AppError::DatabaseFailed(err)
+    }
+}
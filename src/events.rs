@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::State;
+use axum::Json;
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::message::Message;
+use crate::AppState;
+
+/// A summary of a processed message, as returned by `GET /api/v1/events`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProcessedEvent {
+    pub message_id: String,
+    pub cluster_name: String,
+    pub type_url: String,
+    pub received_at: String,
+    pub log_entry: String,
+}
+
+impl From<&Message> for ProcessedEvent {
+    fn from(message: &Message) -> Self {
+        ProcessedEvent {
+            message_id: message.message_id().to_string(),
+            cluster_name: message.attributes.cluster_name.clone(),
+            type_url: message.attributes.type_url.clone(),
+            received_at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            log_entry: message.log_entry(),
+        }
+    }
+}
+
+/// A ring buffer of the most recently processed events.
+#[derive(Clone, Debug)]
+pub struct EventHistory {
+    events: Arc<Mutex<VecDeque<ProcessedEvent>>>,
+    capacity: usize,
+}
+
+impl EventHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { events: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))), capacity }
+    }
+
+    pub fn push(&self, event: ProcessedEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut events = self.events.lock().unwrap();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    pub fn list(&self) -> Vec<ProcessedEvent> {
+        self.events.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Lists recently processed events, oldest first.
+pub async fn list_events(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({ "events": state.events.list() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(message_id: &str) -> ProcessedEvent {
+        ProcessedEvent {
+            message_id: message_id.to_string(),
+            cluster_name: "test-cluster".to_string(),
+            type_url: "type.googleapis.com/google.container.v1beta1.UpgradeEvent".to_string(),
+            received_at: "2023-01-13T19:51:24.884Z".to_string(),
+            log_entry: String::new(),
+        }
+    }
+
+    #[test]
+    fn capacity() {
+        let history = EventHistory::new(2);
+        for message_id in ["1", "2", "3"] {
+            history.push(event(message_id));
+        }
+
+        let message_ids: Vec<_> = history.list().into_iter().map(|e| e.message_id).collect();
+        assert_eq!(message_ids, ["2", "3"]);
+    }
+
+    #[test]
+    fn disabled() {
+        let history = EventHistory::new(0);
+        history.push(event("1"));
+
+        assert!(history.list().is_empty());
+    }
+}
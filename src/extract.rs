@@ -0,0 +1,100 @@
+use axum::body::Bytes;
+use axum::extract::{rejection::JsonRejection, FromRequest, Request};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+use tracing::{debug, warn};
+
+use crate::message::PubSubMessage;
+
+/// Request bodies are logged up to this many bytes.
+const MAX_LOGGED_BODY_LEN: usize = 1024;
+
+/// Extracts the `PubSubMessage` envelope of a push request. Unlike
+/// `axum::Json`, malformed envelopes are rejected with a structured JSON
+/// error body (see `handle_json_rejection`).
+pub struct PubSubEnvelope(pub PubSubMessage);
+
+impl<S: Send + Sync> FromRequest<S> for PubSubEnvelope {
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let json_content_type = has_json_content_type(req.headers());
+        let content_type = req.headers().get(header::CONTENT_TYPE).cloned();
+        let body = Bytes::from_request(req, state).await.map_err(IntoResponse::into_response)?;
+
+        if !json_content_type {
+            return Err(handle_content_type_mismatch(content_type.as_ref(), &body));
+        }
+
+        match Json::<PubSubMessage>::from_bytes(&body) {
+            Ok(Json(psm)) => Ok(PubSubEnvelope(psm)),
+            Err(rejection) => Err(handle_json_rejection(rejection, &body)),
+        }
+    }
+}
+
+/// Logs the raw body of a request without a JSON `Content-Type`, then
+/// responds with HTTP 400 (rather than axum's 415) describing the expected
+/// header, as some Pub/Sub proxies are misconfigured to send `text/plain`.
+fn handle_content_type_mismatch(content_type: Option<&HeaderValue>, body: &[u8]) -> Response {
+    let content_type = content_type.and_then(|value| value.to_str().ok()).unwrap_or("none");
+    warn!(body = truncate(body), "pub/sub envelope with Content-Type: {content_type}");
+
+    let detail = format!(
+        "Expected request with `Content-Type: application/json`, received `{content_type}`"
+    );
+    let body = json!({ "error": "invalid_content_type", "detail": detail });
+    (StatusCode::BAD_REQUEST, Json(body)).into_response()
+}
+
+/// Logs the raw body of a request which couldn't be deserialized, then
+/// responds with HTTP 400 and the deserialization error.
+pub fn handle_json_rejection(rejection: JsonRejection, body: &[u8]) -> Response {
+    let detail = rejection.body_text();
+    debug!(body = truncate(body), "invalid pub/sub envelope: {detail}");
+
+    let body = json!({ "error": "invalid_pubsub_envelope", "detail": detail });
+    (StatusCode::BAD_REQUEST, Json(body)).into_response()
+}
+
+/// The body as (lossy) UTF-8 for logging, truncated to `MAX_LOGGED_BODY_LEN`
+/// bytes.
+fn truncate(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+    if body.len() <= MAX_LOGGED_BODY_LEN {
+        return body.into_owned();
+    }
+
+    let mut end = MAX_LOGGED_BODY_LEN;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…[truncated]", &body[..end])
+}
+
+fn has_json_content_type(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase())
+        .is_some_and(|mime| mime == "application/json" || mime.ends_with("+json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_body() {
+        assert_eq!(truncate(b"{}"), "{}");
+        assert_eq!(truncate(&[b'a'; 1024]), "a".repeat(1024));
+        assert_eq!(truncate(&[b'a'; 1025]), format!("{}…[truncated]", "a".repeat(1024)));
+
+        // A multibyte character across the limit isn't split
+        let body = format!("{}é", "a".repeat(1023));
+        assert_eq!(truncate(body.as_bytes()), format!("{}…[truncated]", "a".repeat(1023)));
+    }
+}
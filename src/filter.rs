@@ -0,0 +1,108 @@
+use regex::Regex;
+use serde::{Serialize, Serializer};
+
+/// The locations of `CLUSTER_LOCATION_FILTER`, for deployments of the service
+/// per region. A location matches when it starts with one of the prefixes,
+/// so a region (`us-central1`) matches its zones (`us-central1-a`) too.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct LocationFilter {
+    prefixes: Vec<String>,
+}
+
+impl LocationFilter {
+    /// Parses a comma separated list of location prefixes.
+    pub fn new(prefixes: &str) -> Self {
+        let prefixes = prefixes.split(',').map(str::trim).filter(|prefix| !prefix.is_empty());
+        Self { prefixes: prefixes.map(String::from).collect() }
+    }
+
+    /// Whether messages for clusters in the location should be processed,
+    /// always true when the filter is empty.
+    pub fn matches(&self, location: &str) -> bool {
+        self.prefixes.is_empty() || self.prefixes.iter().any(|prefix| location.starts_with(prefix))
+    }
+}
+
+/// The pattern of `CLUSTER_NAME_REGEX`, messages for clusters with names it
+/// doesn't match are dropped. Unanchored, so `^` and `$` are needed to match
+/// the whole name.
+#[derive(Clone, Debug, Default)]
+pub struct RegexFilter {
+    regex: Option<Regex>,
+}
+
+impl RegexFilter {
+    /// Compiles the pattern, an empty filter when there is none.
+    pub fn new(pattern: Option<&str>) -> Result<Self, regex::Error> {
+        let regex = pattern.filter(|pattern| !pattern.is_empty()).map(Regex::new).transpose()?;
+        Ok(Self { regex })
+    }
+
+    pub fn pattern(&self) -> Option<&str> {
+        self.regex.as_ref().map(Regex::as_str)
+    }
+
+    /// Whether messages for the cluster should be processed, always true when
+    /// the filter is empty.
+    pub fn matches(&self, cluster_name: &str) -> bool {
+        self.regex.as_ref().is_none_or(|regex| regex.is_match(cluster_name))
+    }
+}
+
+impl Serialize for RegexFilter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.pattern().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches() {
+        let filter = LocationFilter::new("us-central1, europe-west1");
+
+        assert!(filter.matches("us-central1"), "exact region");
+        assert!(filter.matches("us-central1-a"), "zone of a region");
+        assert!(filter.matches("europe-west1-b"));
+        assert!(!filter.matches("europe-west4"));
+        assert!(!filter.matches("us-east1-c"));
+        assert!(!filter.matches(""));
+
+        let filter = LocationFilter::new("asia-");
+        assert!(filter.matches("asia-northeast1"), "prefix");
+        assert!(!filter.matches("australia-southeast1"));
+    }
+
+    #[test]
+    fn empty() {
+        for filter in
+            [LocationFilter::default(), LocationFilter::new(""), LocationFilter::new(" , ")]
+        {
+            assert!(filter.matches("us-central1"));
+            assert!(filter.matches("europe-west1-b"));
+        }
+    }
+
+    #[test]
+    fn regex() {
+        let filter = RegexFilter::new(Some("^prod-.*-(eu|us)$")).unwrap();
+
+        assert_eq!(filter.pattern(), Some("^prod-.*-(eu|us)$"));
+        assert!(filter.matches("prod-payments-eu"));
+        assert!(filter.matches("prod-web-us"));
+        assert!(!filter.matches("staging-web-us"));
+        assert!(!filter.matches("prod-web-asia"));
+
+        let filter = RegexFilter::new(Some("prod")).unwrap();
+        assert!(filter.matches("my-prod-cluster"), "unanchored");
+
+        for filter in [RegexFilter::default(), RegexFilter::new(None).unwrap()] {
+            assert_eq!(filter.pattern(), None);
+            assert!(filter.matches("any-cluster"));
+        }
+        assert!(RegexFilter::new(Some("prod-(")).is_err());
+    }
+}
@@ -0,0 +1,423 @@
+use base64::prelude::*;
+use reqwest::{RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::AppError;
+use crate::message::attributes::payload::SecurityBulletinEvent;
+
+const SECURITY_MD: &str = "SECURITY.md";
+
+/// Proposes updates to `SECURITY.md` in a GitHub repository through the
+/// REST API, see <https://docs.github.com/en/rest>.
+pub struct GitHubClient {
+    client: reqwest::Client,
+    api_url: String,
+    token: String,
+
+    /// The repository as `owner/repo`.
+    repo: String,
+}
+
+impl GitHubClient {
+    pub fn new(client: reqwest::Client, api_url: &str, token: &str, repo: &str) -> Self {
+        GitHubClient {
+            client,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            repo: repo.to_string(),
+        }
+    }
+
+    /// Appends the bulletin to `SECURITY.md` on a new branch and opens a pull
+    /// request for it. Returns the URL of the pull request, or `None` when a
+    /// pull request for the bulletin already exists since it was proposed
+    /// before (GKE sends bulletins for each affected cluster).
+    ///
+    /// A branch left behind by an earlier attempt which failed before opening
+    /// the pull request is reset to the default branch and reused.
+    pub async fn propose_security_md_update(
+        &self,
+        bulletin: &SecurityBulletinEvent,
+    ) -> Result<Option<String>, AppError> {
+        #[derive(Deserialize)]
+        struct Repository {
+            default_branch: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Ref {
+            object: RefObject,
+        }
+
+        #[derive(Deserialize)]
+        struct RefObject {
+            sha: String,
+        }
+
+        #[derive(Deserialize)]
+        struct PullRequest {
+            html_url: String,
+        }
+
+        let repo = &self.repo;
+        let branch = format!("gke-security-bulletin-{}", bulletin.bulletin_id.to_lowercase());
+
+        let owner = repo.split('/').next().unwrap_or(repo);
+        let pulls: Vec<PullRequest> = self
+            .send(self.get(&format!("/repos/{repo}/pulls?state=all&head={owner}:{branch}")))
+            .await?;
+        if !pulls.is_empty() {
+            return Ok(None);
+        }
+
+        let base: Repository = self.send(self.get(&format!("/repos/{repo}"))).await?;
+        let base = base.default_branch;
+        let head: Ref = self.send(self.get(&format!("/repos/{repo}/git/ref/heads/{base}"))).await?;
+
+        let create_ref = self.request(reqwest::Method::POST, &format!("/repos/{repo}/git/refs"));
+        let body = json!({ "ref": format!("refs/heads/{branch}"), "sha": head.object.sha });
+        match self.send::<Value>(create_ref.body(body.to_string())).await {
+            // The branch exists without a pull request, so an earlier attempt failed
+            Err(AppError::HttpPostFailed { status: 422, .. }) => {
+                let path = format!("/repos/{repo}/git/refs/heads/{branch}");
+                let update_ref = self.request(reqwest::Method::PATCH, &path);
+                let body = json!({ "sha": head.object.sha, "force": true });
+                self.send::<Value>(update_ref.body(body.to_string())).await?
+            }
+            res => res?,
+        };
+
+        let (content, sha) = self.security_md(&base).await?;
+        let content = format!("{}{}", content, security_md_entry(bulletin));
+        let mut body = json!({
+            "message": format!("Add GKE security bulletin {}", bulletin.bulletin_id),
+            "content": BASE64_STANDARD.encode(content),
+            "branch": branch,
+        });
+        if let Some(sha) = sha {
+            body["sha"] = json!(sha);
+        }
+        let update =
+            self.request(reqwest::Method::PUT, &format!("/repos/{repo}/contents/{SECURITY_MD}"));
+        self.send::<Value>(update.body(body.to_string())).await?;
+
+        let create_pull = self.request(reqwest::Method::POST, &format!("/repos/{repo}/pulls"));
+        let body = json!({
+            "title": format!("Add GKE security bulletin {}", bulletin.bulletin_id),
+            "head": branch,
+            "base": base,
+            "body": format!(
+                "GKE security bulletin [{}]({}) affects clusters in this project.\n\n{}",
+                bulletin.bulletin_id, bulletin.bulletin_uri, bulletin.brief_description
+            ),
+        });
+        let pull: PullRequest = self.send(create_pull.body(body.to_string())).await?;
+
+        Ok(Some(pull.html_url))
+    }
+
+    /// The content and blob SHA of `SECURITY.md` on `branch`, or an empty
+    /// content and no SHA when the file doesn't exist yet.
+    async fn security_md(&self, branch: &str) -> Result<(String, Option<String>), AppError> {
+        #[derive(Deserialize)]
+        struct Contents {
+            content: String,
+            sha: String,
+        }
+
+        let path = format!("/repos/{}/contents/{SECURITY_MD}?ref={branch}", self.repo);
+        let contents: Contents = match self.send(self.get(&path)).await {
+            Err(AppError::HttpGetFailed { status: 404, .. }) => return Ok((String::new(), None)),
+            res => res?,
+        };
+
+        // The content is base64 encoded with line breaks
+        let encoded: String = contents.content.split_whitespace().collect();
+        let content = BASE64_STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|content| String::from_utf8(content).ok())
+            .ok_or_else(|| AppError::GitHubApiFailed(format!("{SECURITY_MD} isn't valid UTF-8")))?;
+
+        Ok((content, Some(contents.sha)))
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.request(reqwest::Method::GET, path)
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{path}", self.api_url))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "gke-cluster-notifications")
+    }
+
+    async fn send<T: for<'de> Deserialize<'de>>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, AppError> {
+        let request = request.build()?;
+        let is_get = request.method() == reqwest::Method::GET;
+        let resp = self.client.execute(request).await?;
+        let status = resp.status();
+        let text = resp.text().await?;
+
+        match status {
+            StatusCode::OK | StatusCode::CREATED => Ok(serde_json::from_str(&text)?),
+            _ if is_get => Err(AppError::HttpGetFailed { status: status.as_u16(), body: text }),
+            _ => Err(AppError::HttpPostFailed { status: status.as_u16(), body: text }),
+        }
+    }
+}
+
+/// The section appended to `SECURITY.md` for the bulletin.
+fn security_md_entry(bulletin: &SecurityBulletinEvent) -> String {
+    let mut entry = format!(
+        "\n## {} ({})\n\n{}\n\n- Bulletin: {}\n",
+        bulletin.bulletin_id, bulletin.severity, bulletin.brief_description, bulletin.bulletin_uri
+    );
+    if !bulletin.cve_ids.is_empty() {
+        entry.push_str(&format!("- CVEs: {}\n", bulletin.cve_ids.join(", ")));
+    }
+    if !bulletin.patched_versions.is_empty() {
+        entry.push_str(&format!("- Patched versions: {}\n", bulletin.patched_versions.join(", ")));
+    }
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn bulletin() -> SecurityBulletinEvent {
+        SecurityBulletinEvent {
+            bulletin_id: "GCP-2022-005".to_string(),
+            bulletin_uri:
+                "https://cloud.google.com/kubernetes-engine/docs/security-bulletins#gcp-2022-005"
+                    .to_string(),
+            brief_description: "A security vulnerability has been discovered.".to_string(),
+            severity: "Medium".to_string(),
+            cve_ids: vec!["CVE-2021-43527".to_string()],
+            patched_versions: vec!["1.22.6-gke.600".to_string()],
+            ..Default::default()
+        }
+    }
+
+    /// A mock of the GitHub API for the repository `acme/infra` with the
+    /// given pull requests for the bulletin's branch.
+    async fn github(pulls: Value) -> MockServer {
+        let github = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/acme/infra/pulls"))
+            .and(query_param("head", "acme:gke-security-bulletin-gcp-2022-005"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(pulls))
+            .mount(&github)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/acme/infra"))
+            .and(header("Authorization", "Bearer test-token"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "default_branch": "main" })),
+            )
+            .mount(&github)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/acme/infra/git/ref/heads/main"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "object": { "sha": "abc123" } })),
+            )
+            .mount(&github)
+            .await;
+        github
+    }
+
+    #[tokio::test]
+    async fn propose_security_md_update() {
+        let github = github(json!([])).await;
+        Mock::given(method("POST"))
+            .and(path("/repos/acme/infra/git/refs"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({})))
+            .expect(1)
+            .mount(&github)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/acme/infra/contents/SECURITY.md"))
+            .and(query_param("ref", "main"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "content": BASE64_STANDARD.encode("# Security\n"),
+                "sha": "def456",
+            })))
+            .mount(&github)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/repos/acme/infra/contents/SECURITY.md"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&github)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/repos/acme/infra/pulls"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "html_url": "https://github.com/acme/infra/pull/1",
+            })))
+            .expect(1)
+            .mount(&github)
+            .await;
+
+        let client =
+            GitHubClient::new(reqwest::Client::new(), &github.uri(), "test-token", "acme/infra");
+        let pull = client.propose_security_md_update(&bulletin()).await.unwrap();
+        assert_eq!(pull.as_deref(), Some("https://github.com/acme/infra/pull/1"));
+
+        let requests = github.received_requests().await.unwrap();
+        let body = |method: &str, path: &str| {
+            let request = requests
+                .iter()
+                .find(|request| request.method.as_str() == method && request.url.path() == path)
+                .unwrap();
+            serde_json::from_slice::<Value>(&request.body).unwrap()
+        };
+
+        let create_ref = body("POST", "/repos/acme/infra/git/refs");
+        assert_eq!(create_ref["ref"], "refs/heads/gke-security-bulletin-gcp-2022-005");
+        assert_eq!(create_ref["sha"], "abc123");
+
+        let update = body("PUT", "/repos/acme/infra/contents/SECURITY.md");
+        assert_eq!(update["sha"], "def456");
+        assert_eq!(update["branch"], "gke-security-bulletin-gcp-2022-005");
+        let content = BASE64_STANDARD.decode(update["content"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            String::from_utf8(content).unwrap(),
+            "# Security\n\n## GCP-2022-005 (Medium)\n\n\
+             A security vulnerability has been discovered.\n\n\
+             - Bulletin: https://cloud.google.com/kubernetes-engine/docs/security-bulletins#gcp-2022-005\n\
+             - CVEs: CVE-2021-43527\n\
+             - Patched versions: 1.22.6-gke.600\n"
+        );
+
+        let create_pull = body("POST", "/repos/acme/infra/pulls");
+        assert_eq!(create_pull["head"], "gke-security-bulletin-gcp-2022-005");
+        assert_eq!(create_pull["base"], "main");
+    }
+
+    #[tokio::test]
+    async fn new_security_md() {
+        let github = github(json!([])).await;
+        Mock::given(method("POST"))
+            .and(path("/repos/acme/infra/git/refs"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({})))
+            .mount(&github)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/acme/infra/contents/SECURITY.md"))
+            .respond_with(
+                ResponseTemplate::new(404).set_body_json(json!({ "message": "Not Found" })),
+            )
+            .mount(&github)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/repos/acme/infra/contents/SECURITY.md"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({})))
+            .mount(&github)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/repos/acme/infra/pulls"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "html_url": "https://github.com/acme/infra/pull/2",
+            })))
+            .mount(&github)
+            .await;
+
+        let client =
+            GitHubClient::new(reqwest::Client::new(), &github.uri(), "test-token", "acme/infra");
+        client.propose_security_md_update(&bulletin()).await.unwrap().unwrap();
+
+        let requests = github.received_requests().await.unwrap();
+        let update = requests.iter().find(|request| request.method.as_str() == "PUT").unwrap();
+        let update = serde_json::from_slice::<Value>(&update.body).unwrap();
+        assert!(update.get("sha").is_none());
+    }
+
+    #[tokio::test]
+    async fn already_proposed() {
+        let github = github(json!([{ "html_url": "https://github.com/acme/infra/pull/1" }])).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&github)
+            .await;
+
+        let client =
+            GitHubClient::new(reqwest::Client::new(), &github.uri(), "test-token", "acme/infra");
+        assert_eq!(client.propose_security_md_update(&bulletin()).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn retry_after_failed_attempt() {
+        let github = github(json!([])).await;
+        Mock::given(method("POST"))
+            .and(path("/repos/acme/infra/git/refs"))
+            .respond_with(
+                ResponseTemplate::new(422)
+                    .set_body_json(json!({ "message": "Reference already exists" })),
+            )
+            .mount(&github)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/repos/acme/infra/git/refs/heads/gke-security-bulletin-gcp-2022-005"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&github)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/acme/infra/contents/SECURITY.md"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&github)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/repos/acme/infra/contents/SECURITY.md"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({})))
+            .mount(&github)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/repos/acme/infra/pulls"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "html_url": "https://github.com/acme/infra/pull/3",
+            })))
+            .mount(&github)
+            .await;
+
+        let client =
+            GitHubClient::new(reqwest::Client::new(), &github.uri(), "test-token", "acme/infra");
+        let pull = client.propose_security_md_update(&bulletin()).await.unwrap();
+        assert_eq!(pull.as_deref(), Some("https://github.com/acme/infra/pull/3"));
+
+        let requests = github.received_requests().await.unwrap();
+        let update_ref =
+            requests.iter().find(|request| request.method.as_str() == "PATCH").unwrap();
+        let update_ref = serde_json::from_slice::<Value>(&update_ref.body).unwrap();
+        assert_eq!(update_ref, json!({ "sha": "abc123", "force": true }));
+    }
+
+    #[tokio::test]
+    async fn failed_request() {
+        let github = github(json!([])).await;
+        Mock::given(method("POST"))
+            .and(path("/repos/acme/infra/git/refs"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&github)
+            .await;
+
+        let client =
+            GitHubClient::new(reqwest::Client::new(), &github.uri(), "test-token", "acme/infra");
+        match client.propose_security_md_update(&bulletin()).await {
+            Err(AppError::HttpPostFailed { status: 403, .. }) => {}
+            res => panic!("expected AppError::HttpPostFailed, got {res:?}"),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use gcp_auth::TokenProvider;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::error::AppError;
+use crate::message::attributes::payload::ResourceType;
+use crate::message::Message;
+
+const CONTAINER_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// An upgrade of a cluster's control plane, or one of its node pools, to the
+/// version of an `UpgradeAvailableEvent`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClusterUpgrade {
+    pub project_id: String,
+    pub location: String,
+    pub cluster_name: String,
+    pub node_pool_name: Option<String>,
+    pub version: String,
+}
+
+impl ClusterUpgrade {
+    /// The upgrade offered by an `UpgradeAvailableEvent` message, `None` for
+    /// other messages.
+    pub fn for_message(message: &Message) -> Option<Self> {
+        let attr = &message.attributes;
+        let p = attr.payload.as_upgrade_available_event()?;
+        let node_pool_name = match p.resource_type {
+            ResourceType::NodePool => Some(p.node_pool_name()?),
+            _ => None,
+        };
+
+        Some(Self {
+            project_id: attr.project_id.clone(),
+            location: attr.cluster_location.clone(),
+            cluster_name: attr.cluster_name.clone(),
+            node_pool_name,
+            version: p.version.clone(),
+        })
+    }
+
+    fn cluster(&self) -> String {
+        format!(
+            "projects/{}/locations/{}/clusters/{}",
+            self.project_id, self.location, self.cluster_name
+        )
+    }
+}
+
+impl std::fmt::Display for ClusterUpgrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.node_pool_name {
+            Some(node_pool_name) => {
+                write!(f, "{} node pool {node_pool_name} to {}", self.cluster_name, self.version)
+            }
+            None => write!(f, "{} control plane to {}", self.cluster_name, self.version),
+        }
+    }
+}
+
+/// Starts upgrades through the GKE API, see
+/// <https://cloud.google.com/kubernetes-engine/docs/reference/rest/v1/projects.locations.clusters/update>.
+pub struct GkeClient {
+    client: reqwest::Client,
+    endpoint: String,
+    auth: Option<Arc<dyn TokenProvider>>,
+}
+
+impl GkeClient {
+    /// Creates a client for the API at `endpoint`, authenticating with the
+    /// tokens of `auth`.
+    pub fn new(client: reqwest::Client, endpoint: &str, auth: Arc<dyn TokenProvider>) -> Self {
+        Self { client, endpoint: endpoint.trim_end_matches('/').to_string(), auth: Some(auth) }
+    }
+
+    /// Starts the upgrade, returning the name of the operation.
+    pub async fn upgrade(&self, upgrade: &ClusterUpgrade) -> Result<String, AppError> {
+        #[derive(Deserialize)]
+        struct Operation {
+            name: String,
+        }
+
+        let update = match &upgrade.node_pool_name {
+            Some(node_pool_name) => json!({
+                "desiredNodePoolId": node_pool_name,
+                "desiredNodeVersion": upgrade.version,
+            }),
+            None => json!({ "desiredMasterVersion": upgrade.version }),
+        };
+
+        let mut request = self
+            .client
+            .put(format!("{}/v1/{}", self.endpoint, upgrade.cluster()))
+            .header("Content-Type", "application/json")
+            .body(json!({ "update": update }).to_string());
+        if let Some(auth) = &self.auth {
+            let token = auth.token(&[CONTAINER_SCOPE]).await?;
+            request = request.bearer_auth(token.as_str());
+        }
+
+        let resp = request.send().await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(AppError::HttpPostFailed { status: status.as_u16(), body });
+        }
+
+        Ok(serde_json::from_str::<Operation>(&body)?.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::tests::test_messages;
+    use serde_json::Value;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn gke_client(server: &MockServer) -> GkeClient {
+        GkeClient { client: reqwest::Client::new(), endpoint: server.uri(), auth: None }
+    }
+
+    fn upgrade(node_pool_name: Option<&str>) -> ClusterUpgrade {
+        ClusterUpgrade {
+            project_id: "0123456789".to_string(),
+            location: "us-central1".to_string(),
+            cluster_name: "test-cluster".to_string(),
+            node_pool_name: node_pool_name.map(String::from),
+            version: "1.29.4-gke.1043002".to_string(),
+        }
+    }
+
+    #[test]
+    fn for_message() {
+        for test in test_messages() {
+            let upgrade = ClusterUpgrade::for_message(&test.message);
+            let Some(p) = test.message.attributes.payload.as_upgrade_available_event() else {
+                assert_eq!(upgrade, None, "{}", test.name);
+                continue;
+            };
+
+            let upgrade = upgrade.unwrap();
+            assert_eq!(
+                upgrade.cluster(),
+                "projects/0123456789/locations/us-central1/clusters/test-cluster"
+            );
+            assert_eq!(upgrade.version, p.version, "{}", test.name);
+            assert_eq!(upgrade.node_pool_name, p.node_pool_name(), "{}", test.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn upgrade_control_plane() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/projects/0123456789/locations/us-central1/clusters/test-cluster"))
+            .and(body_json(json!({ "update": { "desiredMasterVersion": "1.29.4-gke.1043002" } })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": "operation-1",
+                "operationType": "UPGRADE_MASTER",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let operation = gke_client(&server).upgrade(&upgrade(None)).await.unwrap();
+        assert_eq!(operation, "operation-1");
+    }
+
+    #[tokio::test]
+    async fn upgrade_node_pool() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "name": "operation-2" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let operation = gke_client(&server).upgrade(&upgrade(Some("default-pool"))).await.unwrap();
+        assert_eq!(operation, "operation-2");
+
+        let requests = server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "update": {
+                    "desiredNodePoolId": "default-pool",
+                    "desiredNodeVersion": "1.29.4-gke.1043002",
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn upgrade_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(403).set_body_string("PERMISSION_DENIED"))
+            .mount(&server)
+            .await;
+
+        match gke_client(&server).upgrade(&upgrade(None)).await {
+            Err(AppError::HttpPostFailed { status: 403, body }) => {
+                assert_eq!(body, "PERMISSION_DENIED")
+            }
+            res => panic!("expected AppError::HttpPostFailed, got {res:?}"),
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use lru::LruCache;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use tracing::{error, info, warn};
+
+use crate::gke::{ClusterUpgrade, GkeClient};
+use crate::message::Message;
+use crate::AppState;
+
+/// The prefix of the `action_id` of "Approve Upgrade" buttons.
+const APPROVE_UPGRADE: &str = "approve_upgrade:";
+
+/// Requests signed longer ago than this are rejected, to prevent replays.
+const MAX_SIGNATURE_AGE_SECS: i64 = 5 * 60;
+
+/// Interaction payloads are a few kilobytes, larger requests are rejected
+/// before their signature is checked.
+pub const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Upgrades offered longer ago than this can no longer be approved, as a
+/// newer version is usually available by then.
+const MAX_PENDING_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+const PENDING_CAPACITY: NonZeroUsize = NonZeroUsize::new(1000).unwrap();
+
+/// The upgrades offered by "Approve Upgrade" buttons posted to Slack, by the
+/// `action_id` of the button. An upgrade is removed once it's approved, when
+/// it's offered longer ago than `MAX_PENDING_AGE` or when more than
+/// `PENDING_CAPACITY` upgrades are pending.
+#[derive(Clone, Debug)]
+pub struct PendingUpgrades {
+    upgrades: Arc<Mutex<LruCache<String, (ClusterUpgrade, Instant)>>>,
+}
+
+impl Default for PendingUpgrades {
+    fn default() -> Self {
+        Self { upgrades: Arc::new(Mutex::new(LruCache::new(PENDING_CAPACITY))) }
+    }
+}
+
+impl PendingUpgrades {
+    /// Adds the upgrade offered by an `UpgradeAvailableEvent` message,
+    /// returning the `action_id` of its button. Repeated messages for the
+    /// same upgrade share the `action_id`.
+    pub fn insert(&self, message: &Message) -> Option<String> {
+        let upgrade = ClusterUpgrade::for_message(message)?;
+        let action_id =
+            format!("{APPROVE_UPGRADE}{}:{}", message.attributes.resource_uri(), upgrade.version);
+        self.upgrades.lock().unwrap().put(action_id.clone(), (upgrade, Instant::now()));
+        Some(action_id)
+    }
+
+    fn take(&self, action_id: &str) -> Option<ClusterUpgrade> {
+        self.take_at(action_id, Instant::now())
+    }
+
+    fn take_at(&self, action_id: &str, now: Instant) -> Option<ClusterUpgrade> {
+        let (upgrade, offered) = self.upgrades.lock().unwrap().pop(action_id)?;
+        (now.saturating_duration_since(offered) < MAX_PENDING_AGE).then_some(upgrade)
+    }
+
+    fn restore(&self, action_id: &str, upgrade: ClusterUpgrade) {
+        self.upgrades.lock().unwrap().put(action_id.to_string(), (upgrade, Instant::now()));
+    }
+}
+
+#[derive(Deserialize)]
+struct InteractionForm {
+    payload: String,
+}
+
+/// The parts of a `block_actions` payload sent when a button is clicked, see
+/// <https://api.slack.com/reference/interaction-payloads/block-actions>.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct InteractionPayload {
+    user: SlackUser,
+    actions: Vec<Action>,
+    response_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SlackUser {
+    id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Action {
+    action_id: String,
+}
+
+/// Handles Slack interactive payloads (`POST /slack/interaction`). When an
+/// "Approve Upgrade" button is clicked, the upgrade is started through the
+/// GKE API and the outcome is posted as a reply to the message.
+///
+/// Requests must be signed with `SLACK_SIGNING_SECRET`, otherwise HTTP 401
+/// is returned. Without a signing secret, interactions aren't enabled and
+/// HTTP 404 is returned. During a `DRY_RUN` the approved upgrade is only
+/// logged.
+pub async fn slack_interaction(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let config = state.config.load();
+    let Some(signing_secret) = &config.slack_signing_secret else {
+        return StatusCode::NOT_FOUND;
+    };
+    if !is_valid_signature(signing_secret, &headers, &body, Utc::now().timestamp()) {
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let Some(payload) = serde_urlencoded::from_bytes::<InteractionForm>(&body)
+        .ok()
+        .and_then(|form| serde_json::from_str::<InteractionPayload>(&form.payload).ok())
+    else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    for action in &payload.actions {
+        if !action.action_id.starts_with(APPROVE_UPGRADE) {
+            continue;
+        }
+
+        if config.dry_run {
+            info!(user = payload.user.id, "dry run, not approving {}", action.action_id);
+            continue;
+        }
+
+        let reply =
+            approve_upgrade(&state, &config.gke_api_url, &action.action_id, &payload.user).await;
+        if let Some(response_url) = &payload.response_url {
+            let body = json!({ "replace_original": false, "text": reply });
+            if let Err(err) = state
+                .http
+                .post(response_url)
+                .header("Content-Type", "application/json")
+                .body(body.to_string())
+                .send()
+                .await
+            {
+                warn!("reply to slack interaction failed: {err}");
+            }
+        }
+    }
+
+    StatusCode::OK
+}
+
+/// Starts the pending upgrade of the action, returning the reply to post.
+async fn approve_upgrade(
+    state: &AppState,
+    gke_api_url: &str,
+    action_id: &str,
+    user: &SlackUser,
+) -> String {
+    let Some(upgrade) = state.pending_upgrades.take(action_id) else {
+        return "This upgrade is no longer pending, it has already been approved (or the service \
+         restarted since it was posted)."
+            .to_string();
+    };
+
+    let result = match state.token_provider().await {
+        Ok(auth) => GkeClient::new(state.http.clone(), gke_api_url, auth).upgrade(&upgrade).await,
+        Err(err) => Err(err),
+    };
+
+    match result {
+        Ok(operation) => {
+            info!(operation, user = user.id, "upgrade of {upgrade} approved");
+            format!(":white_check_mark: <@{}> approved the upgrade of {upgrade}", user.id)
+        }
+        Err(err) => {
+            error!(user = user.id, "upgrade of {upgrade} failed: {err}");
+            let reply = format!(":x: The upgrade of {upgrade} failed: {err}");
+            state.pending_upgrades.restore(action_id, upgrade);
+            reply
+        }
+    }
+}
+
+/// Verifies the `X-Slack-Signature` of a request, see
+/// <https://api.slack.com/authentication/verifying-requests-from-slack>.
+fn is_valid_signature(signing_secret: &str, headers: &HeaderMap, body: &[u8], now: i64) -> bool {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let (Some(timestamp), Some(signature)) =
+        (header("X-Slack-Request-Timestamp"), header("X-Slack-Signature"))
+    else {
+        return false;
+    };
+    match timestamp.parse::<i64>() {
+        Ok(signed_at) if (now - signed_at).abs() <= MAX_SIGNATURE_AGE_SECS => {}
+        _ => return false,
+    }
+
+    let expected = sign(signing_secret, timestamp, body);
+    bool::from(signature.as_bytes().ct_eq(expected.as_bytes()))
+}
+
+fn sign(signing_secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(signing_secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("v0:{timestamp}:").as_bytes());
+    mac.update(body);
+
+    let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{b:02x}")).collect();
+    format!("v0={hex}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::message::tests::test_messages;
+    use axum::body::Body;
+    use axum::http::Request;
+    use serde_json::Value;
+    use tower::ServiceExt;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SIGNING_SECRET: &str = "8f742231b10e8888abcd99yyyzzz85a5";
+
+    fn signed_request(body: &str, timestamp: i64) -> Request<Body> {
+        let timestamp = timestamp.to_string();
+        Request::post("/slack/interaction")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("X-Slack-Request-Timestamp", &timestamp)
+            .header("X-Slack-Signature", sign(SIGNING_SECRET, &timestamp, body.as_bytes()))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn block_actions(action_id: &str, response_url: &str) -> String {
+        let payload = json!({
+            "type": "block_actions",
+            "user": { "id": "U0123456789" },
+            "actions": [{ "action_id": action_id, "type": "button" }],
+            "response_url": response_url,
+        });
+        serde_urlencoded::to_string([("payload", payload.to_string())]).unwrap()
+    }
+
+    #[test]
+    fn signature() {
+        // The example from the Slack documentation
+        let body = "token=xyzz0WbapA4vBCDEFasx0q6G&team_id=T1DC2JH3J&team_domain=testteamnow&channel_id=G8PSS9T3V&channel_name=foobar&user_id=U2CERLKJA&user_name=roadrunner&command=%2Fwebhook-collect&text=&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2FT1DC2JH3J%2F397700885554%2F96rGlfmibIGlgcZRskXaIFfN&trigger_id=398738663015.47445629121.803a0bc887a14d10d2c447fce8b6703c";
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Slack-Request-Timestamp", "1531420618".parse().unwrap());
+        headers.insert(
+            "X-Slack-Signature",
+            "v0=a2114d57b48eac39b9ad189dd8316235a7b4a8d21a10bd27519666489c69b503".parse().unwrap(),
+        );
+
+        assert!(is_valid_signature(SIGNING_SECRET, &headers, body.as_bytes(), 1531420618));
+        assert!(!is_valid_signature("wrong-secret", &headers, body.as_bytes(), 1531420618));
+        assert!(!is_valid_signature(SIGNING_SECRET, &headers, b"token=changed", 1531420618));
+        assert!(
+            !is_valid_signature(SIGNING_SECRET, &headers, body.as_bytes(), 1531420618 + 301),
+            "expired signature"
+        );
+        assert!(!is_valid_signature(
+            SIGNING_SECRET,
+            &HeaderMap::new(),
+            body.as_bytes(),
+            1531420618
+        ));
+    }
+
+    #[test]
+    fn pending_upgrades() {
+        let pending = PendingUpgrades::default();
+        for test in test_messages() {
+            let action_id = pending.insert(&test.message);
+            let Some(upgrade) = ClusterUpgrade::for_message(&test.message) else {
+                assert_eq!(action_id, None, "{}", test.name);
+                continue;
+            };
+
+            let action_id = action_id.unwrap();
+            assert!(action_id.starts_with(APPROVE_UPGRADE), "{}", test.name);
+            assert!(action_id.len() <= 255, "Slack limits action_id to 255 characters");
+            assert_eq!(pending.insert(&test.message).as_ref(), Some(&action_id));
+            assert_eq!(pending.take(&action_id), Some(upgrade), "{}", test.name);
+            assert_eq!(pending.take(&action_id), None, "{}", test.name);
+
+            pending.insert(&test.message);
+            let later = Instant::now() + MAX_PENDING_AGE;
+            assert_eq!(pending.take_at(&action_id, later), None, "{} expired", test.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn slack_interaction() {
+        let slack = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&slack)
+            .await;
+
+        let config =
+            Config { slack_signing_secret: Some(SIGNING_SECRET.into()), ..Default::default() };
+        let state = AppState::new(config);
+        let body = block_actions("approve_upgrade:unknown", &slack.uri());
+        let now = Utc::now().timestamp();
+
+        let response = crate::router(state.clone()).oneshot(signed_request(&body, now - 600)).await;
+        assert_eq!(response.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        let response = crate::router(state.clone()).oneshot(signed_request("{}", now)).await;
+        assert_eq!(response.unwrap().status(), StatusCode::BAD_REQUEST);
+
+        let large = "x".repeat(MAX_BODY_BYTES + 1);
+        let response = crate::router(state.clone()).oneshot(signed_request(&large, now)).await;
+        assert_eq!(response.unwrap().status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = crate::router(state).oneshot(signed_request(&body, now)).await;
+        assert_eq!(response.unwrap().status(), StatusCode::OK);
+
+        let requests = slack.received_requests().await.unwrap();
+        let reply: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(reply["replace_original"], false);
+        assert!(reply["text"].as_str().unwrap().contains("no longer pending"));
+    }
+
+    #[tokio::test]
+    async fn slack_interaction_dry_run() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+        Mock::given(method("PUT")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+
+        let config = Config {
+            slack_signing_secret: Some(SIGNING_SECRET.into()),
+            gke_api_url: server.uri(),
+            dry_run: true,
+            ..Default::default()
+        };
+        let state = AppState::new(config);
+        let action_id = test_messages()
+            .into_iter()
+            .find_map(|test| state.pending_upgrades.insert(&test.message))
+            .unwrap();
+        let body = block_actions(&action_id, &server.uri());
+
+        let response =
+            crate::router(state.clone()).oneshot(signed_request(&body, Utc::now().timestamp()));
+        assert_eq!(response.await.unwrap().status(), StatusCode::OK);
+        assert!(server.received_requests().await.unwrap().is_empty());
+        assert!(state.pending_upgrades.take(&action_id).is_some(), "still pending");
+    }
+
+    #[tokio::test]
+    async fn slack_interaction_disabled() {
+        let state = AppState::new(Config::default());
+        let body = block_actions("approve_upgrade:unknown", "http://localhost");
+        let response =
+            crate::router(state).oneshot(signed_request(&body, Utc::now().timestamp())).await;
+        assert_eq!(response.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+}
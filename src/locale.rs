@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// The format strings of a locale, by key.
+#[derive(Debug, Default, Deserialize)]
+struct Translations {
+    plain_text: HashMap<String, String>,
+    log_entry: HashMap<String, String>,
+}
+
+static TRANSLATIONS: LazyLock<[Translations; 4]> = LazyLock::new(|| {
+    [
+        include_str!("locale/en.toml"),
+        include_str!("locale/de.toml"),
+        include_str!("locale/fr.toml"),
+        include_str!("locale/ja.toml"),
+    ]
+    .map(|translations| toml::from_str(translations).expect("invalid translations"))
+});
+
+/// The language of the plain text and log entries of messages, from
+/// `NOTIFICATION_LOCALE`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+    Ja,
+}
+
+impl Locale {
+    /// Formats the `plain_text` string for `key`, see `Message::plain_text`.
+    pub fn plain_text(self, key: &str, args: &[(&str, String)]) -> String {
+        self.format(|translations| &translations.plain_text, key, args)
+    }
+
+    /// Formats the `log_entry` string for `key`, see `Message::log_entry`.
+    pub fn log_entry(self, key: &str, args: &[(&str, String)]) -> String {
+        self.format(|translations| &translations.log_entry, key, args)
+    }
+
+    /// Replaces the `{name}` placeholders of the string for `key` with `args`,
+    /// falling back to English for strings which haven't been translated.
+    fn format(
+        self,
+        strings: fn(&Translations) -> &HashMap<String, String>,
+        key: &str,
+        args: &[(&str, String)],
+    ) -> String {
+        let template = strings(&TRANSLATIONS[self as usize])
+            .get(key)
+            .or_else(|| strings(&TRANSLATIONS[Locale::En as usize]).get(key))
+            .map(String::as_str)
+            .unwrap_or(key);
+
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some((before, after)) = rest.split_once('{') {
+            result.push_str(before);
+            match after.split_once('}') {
+                Some((name, after)) => {
+                    match args.iter().find(|(arg, _)| *arg == name) {
+                        Some((_, value)) => result.push_str(value),
+                        None => result.push_str(&format!("{{{name}}}")),
+                    }
+                    rest = after;
+                }
+                None => {
+                    result.push('{');
+                    rest = after;
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+impl FromStr for Locale {
+    type Err = AppError;
+
+    /// Parses a language code, e.g. `de` or `de-CH`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['-', '_']).next().unwrap_or_default();
+        match language.to_lowercase().as_str() {
+            "en" => Ok(Locale::En),
+            "de" => Ok(Locale::De),
+            "fr" => Ok(Locale::Fr),
+            "ja" => Ok(Locale::Ja),
+            _ => Err(AppError::InvalidConfig(format!("unsupported NOTIFICATION_LOCALE {s}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str() {
+        assert_eq!("en".parse::<Locale>().unwrap(), Locale::En);
+        assert_eq!("de-CH".parse::<Locale>().unwrap(), Locale::De);
+        assert_eq!("FR".parse::<Locale>().unwrap(), Locale::Fr);
+        assert_eq!("ja_JP".parse::<Locale>().unwrap(), Locale::Ja);
+        assert!("sv".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn translations() {
+        let english = &TRANSLATIONS[Locale::En as usize];
+        for locale in [Locale::De, Locale::Fr, Locale::Ja] {
+            let translations = &TRANSLATIONS[locale as usize];
+            for (strings, english) in [
+                (&translations.plain_text, &english.plain_text),
+                (&translations.log_entry, &english.log_entry),
+            ] {
+                let mut missing: Vec<_> =
+                    english.keys().filter(|key| !strings.contains_key(*key)).collect();
+                missing.sort();
+                assert!(missing.is_empty(), "{locale:?} is missing {missing:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn format() {
+        let args = [("cluster", "test-cluster".to_string()), ("status", "{status}".to_string())];
+        assert_eq!(
+            Locale::En.plain_text("cluster_status_change", &args),
+            "test-cluster status changed to {status}"
+        );
+        assert_eq!(
+            Locale::En.plain_text("unknown_type", &[]),
+            "{cluster} received event of unknown type"
+        );
+        assert_eq!(Locale::En.plain_text("missing_key", &args), "missing_key");
+    }
+}
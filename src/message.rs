@@ -1,27 +1,34 @@
 pub mod attributes;
+pub mod datadog;
+pub mod html;
+pub mod notifier;
 pub mod slack;
+pub mod template;
+pub mod webhook_history;
 
 use base64::prelude::*;
-use serde::{de, Deserialize, Deserializer};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use self::attributes::payload::{Payload, ResourceType};
 use self::attributes::Attributes;
+use crate::locale::Locale;
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(default)]
 pub struct PubSubMessage {
     pub message: Message,
     pub subscription: String,
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(default)]
 pub struct Message {
     pub attributes: Attributes,
     message_id: String,
     publish_time: String,
 
-    #[serde(deserialize_with = "from_base64")]
+    #[serde(deserialize_with = "from_base64", serialize_with = "to_base64")]
     data: String,
 }
 
@@ -30,63 +37,219 @@ impl Message {
         Self { attributes: self.attributes.with_project_name(project_name), ..self }
     }
 
+    pub fn with_cluster_auto_upgrade(self, cluster_auto_upgrade: bool) -> Self {
+        Self { attributes: self.attributes.with_cluster_auto_upgrade(cluster_auto_upgrade), ..self }
+    }
+
+    pub fn message_id(&self) -> &str {
+        &self.message_id
+    }
+
+    /// When the message was published to Pub/Sub, if the time is valid.
+    pub fn publish_time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.publish_time).ok().map(|time| time.to_utc())
+    }
+
+    /// How long ago the message was published to Pub/Sub, if the time is
+    /// valid.
+    pub fn age(&self) -> Option<TimeDelta> {
+        self.age_at(Utc::now())
+    }
+
+    /// Whether the message was published longer than `limit` ago.
+    pub fn is_stale(&self, limit: std::time::Duration) -> bool {
+        self.is_stale_at(limit, Utc::now())
+    }
+
+    fn age_at(&self, now: DateTime<Utc>) -> Option<TimeDelta> {
+        self.publish_time().map(|publish_time| now - publish_time)
+    }
+
+    fn is_stale_at(&self, limit: std::time::Duration, now: DateTime<Utc>) -> bool {
+        let limit = TimeDelta::from_std(limit).unwrap_or(TimeDelta::MAX);
+        self.age_at(now).is_some_and(|age| age > limit)
+    }
+
     pub fn is_invalid(&self) -> bool {
         self.data.is_empty() || self.attributes.is_invalid()
     }
 
     pub fn log_entry(&self) -> String {
-        match self.attributes.log_message() {
-            Ok(msg) => msg,
-            Err(err) => {
-                if self.data.is_empty() {
-                    err
-                } else {
-                    format!("{}: {}", err, self.data)
-                }
+        self.log_entry_localized(Locale::En)
+    }
+
+    /// The log entry of the message in `locale`, followed by the data of
+    /// messages which can't be formatted.
+    pub fn log_entry_localized(&self, locale: Locale) -> String {
+        let attr = &self.attributes;
+        let (key, mut args) = match &attr.payload {
+            _ if attr.is_invalid() => ("invalid", vec![]),
+            Payload::SecurityBulletinEvent(p) => {
+                ("security_bulletin", vec![("bulletin_id", p.bulletin_id.clone())])
+            }
+            Payload::UpgradeAvailableEvent(p) => {
+                let key = match &p.resource_type {
+                    ResourceType::ControlPlane => "upgrade_available_control_plane",
+                    ResourceType::NodePool => "upgrade_available_node_pool",
+                    ResourceType::Unknown(_) => "unknown_resource_type",
+                };
+                let args = vec![
+                    ("version", p.version.clone()),
+                    ("release_channel", p.release_channel.to_string()),
+                    ("resource_type", p.resource_type.to_string()),
+                ];
+                (key, args)
+            }
+            Payload::UpgradeEvent(p) => {
+                let key = match &p.resource_type {
+                    ResourceType::ControlPlane => "upgrade_control_plane",
+                    ResourceType::NodePool => "upgrade_node_pool",
+                    ResourceType::Unknown(_) => "unknown_resource_type",
+                };
+                let args = vec![
+                    ("current_version", p.current_version.clone()),
+                    ("version", p.target_version.clone()),
+                    ("resource_type", p.resource_type.to_string()),
+                ];
+                (key, args)
+            }
+            Payload::ClusterStatusChangeEvent(p) => {
+                let key = match p.status_message.is_empty() {
+                    true => "cluster_status_change",
+                    false => "cluster_status_change_message",
+                };
+                let args = vec![
+                    ("status", p.status.to_string()),
+                    ("status_message", p.status_message.clone()),
+                ];
+                (key, args)
+            }
+            Payload::NodeUpgradeCompleteEvent(p) => {
+                let key = match &p.resource_type {
+                    ResourceType::ControlPlane => "node_upgrade_complete_control_plane",
+                    ResourceType::NodePool => "node_upgrade_complete_node_pool",
+                    ResourceType::Unknown(_) => "unknown_resource_type",
+                };
+                let args = vec![
+                    ("current_version", p.current_version.clone()),
+                    ("version", p.target_version.clone()),
+                    ("resource_type", p.resource_type.to_string()),
+                ];
+                (key, args)
+            }
+            Payload::NodePoolConfigChangeEvent(p) => (
+                "node_pool_config_change",
+                vec![
+                    ("node_pool", p.node_pool_name.clone()),
+                    ("changed_fields", p.changed_fields.join(", ")),
+                ],
+            ),
+            Payload::NodePoolAutoscalingEvent(p) => (
+                "node_pool_autoscaling",
+                vec![
+                    ("node_count_change", format!("{:+}", p.node_count_change)),
+                    ("reason", p.reason.clone()),
+                ],
+            ),
+            Payload::NodePoolCreatedEvent(_) => ("node_pool_created", vec![]),
+            Payload::NodePoolDeletedEvent(_) => ("node_pool_deleted", vec![]),
+            _ => ("unknown_type", vec![("type_url", attr.type_url.clone())]),
+        };
+
+        args.push(("resource", attr.resource_uri()));
+        let log_entry = locale.log_entry(key, &args);
+        match key {
+            "invalid" | "unknown_type" if !self.data.is_empty() => {
+                format!("{log_entry}: {}", self.data)
             }
+            _ => log_entry,
         }
     }
 
     fn plain_text(&self) -> String {
+        self.plain_text_localized(Locale::En)
+    }
+
+    /// The one-line summary of the message, in `locale`.
+    pub fn plain_text_localized(&self, locale: Locale) -> String {
         let attr = &self.attributes;
-        match &attr.payload {
-            Payload::SecurityBulletinEvent(p) => format!(
-                "Security bulletin {} affecting {} has been issued",
-                p.bulletin_id, attr.cluster_name
-            ),
+        let (key, mut args) = match &attr.payload {
+            Payload::SecurityBulletinEvent(p) => {
+                ("security_bulletin", vec![("bulletin_id", p.bulletin_id.clone())])
+            }
             Payload::UpgradeAvailableEvent(p) => match &p.resource_type {
-                ResourceType::ControlPlane => format!(
-                    "{} control plane has new version available {}",
-                    attr.cluster_name, p.version,
-                ),
-                ResourceType::NodePool => format!(
-                    "{} node pool {} has new version available {}",
-                    attr.cluster_name,
-                    p.node_pool_name().unwrap_or_default(),
-                    p.version,
+                ResourceType::ControlPlane => {
+                    ("upgrade_available_control_plane", vec![("version", p.version.clone())])
+                }
+                ResourceType::NodePool => (
+                    "upgrade_available_node_pool",
+                    vec![
+                        ("node_pool", p.node_pool_name().unwrap_or_default()),
+                        ("version", p.version.clone()),
+                    ],
                 ),
-                ResourceType::Unknown(str) => {
-                    format!("{} unknown resource type {str}", attr.cluster_name)
+                ResourceType::Unknown(_) => {
+                    ("unknown_resource_type", vec![("resource_type", p.resource_type.to_string())])
                 }
             },
             Payload::UpgradeEvent(p) => match &p.resource_type {
-                ResourceType::ControlPlane => format!(
-                    "{} control plane is upgrading to version {}",
-                    attr.cluster_name, p.target_version
+                ResourceType::ControlPlane => {
+                    ("upgrade_control_plane", vec![("version", p.target_version.clone())])
+                }
+                ResourceType::NodePool => (
+                    "upgrade_node_pool",
+                    vec![
+                        ("node_pool", p.node_pool_name().unwrap_or_default()),
+                        ("version", p.target_version.clone()),
+                    ],
                 ),
-                ResourceType::NodePool => format!(
-                    "{} node pool {} is upgrading to version {}",
-                    attr.cluster_name,
-                    p.node_pool_name().unwrap_or_default(),
-                    p.target_version
+                ResourceType::Unknown(_) => {
+                    ("unknown_resource_type", vec![("resource_type", p.resource_type.to_string())])
+                }
+            },
+            Payload::ClusterStatusChangeEvent(p) => {
+                ("cluster_status_change", vec![("status", p.status.to_string())])
+            }
+            Payload::NodeUpgradeCompleteEvent(p) => match &p.resource_type {
+                ResourceType::ControlPlane => (
+                    "node_upgrade_complete_control_plane",
+                    vec![("version", p.target_version.clone())],
+                ),
+                ResourceType::NodePool => (
+                    "node_upgrade_complete_node_pool",
+                    vec![
+                        ("node_pool", p.node_pool_name().unwrap_or_default()),
+                        ("version", p.target_version.clone()),
+                    ],
                 ),
-                ResourceType::Unknown(str) => {
-                    format!("{} unknown resource type {str}", attr.cluster_name)
+                ResourceType::Unknown(_) => {
+                    ("unknown_resource_type", vec![("resource_type", p.resource_type.to_string())])
                 }
             },
-            _ if self.is_invalid() => "empty or invalid payload".to_string(),
-            _ => format!("{} received event of unknown type", attr.cluster_name),
-        }
+            Payload::NodePoolConfigChangeEvent(p) => {
+                ("node_pool_config_change", vec![("node_pool", p.node_pool_name.clone())])
+            }
+            Payload::NodePoolAutoscalingEvent(p) => {
+                let node_count_change = ("node_count_change", format!("{:+}", p.node_count_change));
+                match p.node_pool_name() {
+                    Some(node_pool) => {
+                        ("node_pool_autoscaling", vec![("node_pool", node_pool), node_count_change])
+                    }
+                    None => ("cluster_autoscaling", vec![node_count_change]),
+                }
+            }
+            Payload::NodePoolCreatedEvent(p) => {
+                ("node_pool_created", vec![("node_pool", p.node_pool_name().unwrap_or_default())])
+            }
+            Payload::NodePoolDeletedEvent(p) => {
+                ("node_pool_deleted", vec![("node_pool", p.node_pool_name().unwrap_or_default())])
+            }
+            _ if self.is_invalid() => ("invalid", vec![]),
+            _ => ("unknown_type", vec![]),
+        };
+
+        args.push(("cluster", attr.cluster_name.clone()));
+        locale.plain_text(key, &args)
     }
 
     fn markdown(&self) -> String {
@@ -105,9 +268,9 @@ impl Message {
                     attr.cluster_name,
                     p.node_pool_name().unwrap_or_default(),
                 ),
-                ResourceType::Unknown(str) => format!(
-                    "*`{}`* unknown resource type `{str}` encountered on `{}`",
-                    attr.cluster_name, attr.payload
+                ResourceType::Unknown(_) => format!(
+                    "*`{}`* unknown resource type `{}` encountered on `{}`",
+                    attr.cluster_name, p.resource_type, attr.payload
                 ),
             },
             Payload::UpgradeEvent(p) => match &p.resource_type {
@@ -119,39 +282,153 @@ impl Message {
                     attr.cluster_name,
                     p.node_pool_name().unwrap_or_default(),
                 ),
-                ResourceType::Unknown(str) => format!(
-                    "*`{}`* unknown resource type `{str}` encountered on `{}`",
-                    attr.cluster_name, attr.payload
+                ResourceType::Unknown(_) => format!(
+                    "*`{}`* unknown resource type `{}` encountered on `{}`",
+                    attr.cluster_name, p.resource_type, attr.payload
+                ),
+            },
+            Payload::ClusterStatusChangeEvent(p) => {
+                format!("*`{}`* status changed to `{}`", attr.cluster_name, p.status)
+            }
+            Payload::NodeUpgradeCompleteEvent(p) => match &p.resource_type {
+                ResourceType::ControlPlane => {
+                    format!("*`{}`* control plane finished upgrading", attr.cluster_name)
+                }
+                ResourceType::NodePool => format!(
+                    "*`{}`* node pool `{}` finished upgrading",
+                    attr.cluster_name,
+                    p.node_pool_name().unwrap_or_default(),
+                ),
+                ResourceType::Unknown(_) => format!(
+                    "*`{}`* unknown resource type `{}` encountered on `{}`",
+                    attr.cluster_name, p.resource_type, attr.payload
+                ),
+            },
+            Payload::NodePoolConfigChangeEvent(p) => format!(
+                "*`{}`* node pool `{}` configuration changed",
+                attr.cluster_name, p.node_pool_name
+            ),
+            Payload::NodePoolAutoscalingEvent(p) => match p.node_pool_name() {
+                Some(node_pool_name) => format!(
+                    "*`{}`* node pool `{node_pool_name}` was autoscaled by {:+} nodes",
+                    attr.cluster_name, p.node_count_change
+                ),
+                None => format!(
+                    "*`{}`* was autoscaled by {:+} nodes",
+                    attr.cluster_name, p.node_count_change
                 ),
             },
+            Payload::NodePoolCreatedEvent(p) => format!(
+                "*`{}`* node pool `{}` was created",
+                attr.cluster_name,
+                p.node_pool_name().unwrap_or_default()
+            ),
+            Payload::NodePoolDeletedEvent(p) => format!(
+                "*`{}`* node pool `{}` was deleted",
+                attr.cluster_name,
+                p.node_pool_name().unwrap_or_default()
+            ),
             _ if self.is_invalid() => "empty or invalid payload".to_string(),
             _ => format!("`{}` received event of unknown type", attr.cluster_name),
         }
     }
 }
 
+/// Decodes the standard base64 used by GKE, falling back to the URL-safe
+/// alphabet used by some Pub/Sub clients.
 fn from_base64<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
 {
-    String::deserialize(deserializer)
-        .and_then(|str| BASE64_STANDARD.decode(str).map_err(de::Error::custom))
-        .map(String::from_utf8)
-        .and_then(|res| res.map_err(de::Error::custom))
+    let str = String::deserialize(deserializer)?;
+    let bytes =
+        BASE64_STANDARD.decode(&str).or_else(|_| BASE64_URL_SAFE.decode(&str)).map_err(|err| {
+            de::Error::custom(format!("data is neither standard nor URL-safe base64: {err}"))
+        })?;
+    String::from_utf8(bytes).map_err(de::Error::custom)
+}
+
+/// Formats a duration such as `1h 2m 3s`, leaving out the units shorter than
+/// `precision` unless the duration is shorter, e.g. `2h 15m` or `30s` with a
+/// precision of a minute. Negative durations are formatted as `0s`.
+pub fn format_duration(duration: TimeDelta, precision: TimeDelta) -> String {
+    let seconds = duration.num_seconds().max(0);
+    let formatted = humantime::format_duration(std::time::Duration::from_secs(seconds as u64));
+    if seconds < precision.num_seconds() {
+        return formatted.to_string();
+    }
+
+    // The length of the units humantime formats whole seconds with
+    let unit_seconds = |part: &str| match part.trim_start_matches(|c: char| c.is_ascii_digit()) {
+        "years" | "year" => 31_557_600,
+        "months" | "month" => 2_630_016,
+        "days" | "day" => 86_400,
+        "h" => 3600,
+        "m" => 60,
+        _ => 1,
+    };
+    formatted
+        .to_string()
+        .split(' ')
+        .filter(|part| unit_seconds(part) >= precision.num_seconds())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn to_base64<S>(data: &str, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&BASE64_STANDARD.encode(data))
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use std::collections::HashMap;
 
     pub struct TestCase {
+        pub name: String,
         pub message: Message,
         log_entry: String,
         plain_text: String,
         markdown: String,
     }
 
+    /// The logs written while the guard of `capture_logs` is alive.
+    #[derive(Clone, Default)]
+    pub struct Logs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Logs {
+        pub fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl std::io::Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Captures the logs of the current thread, e.g. within a
+    /// `#[tokio::test]`, until the returned guard is dropped.
+    pub fn capture_logs() -> (Logs, tracing::subscriber::DefaultGuard) {
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .finish();
+        (logs, tracing::subscriber::set_default(subscriber))
+    }
+
     pub fn test_messages() -> Vec<TestCase> {
         serde_yaml::from_slice::<Vec<HashMap<String, String>>>(include_bytes!("message/tests.yaml"))
             .unwrap()
@@ -166,6 +443,7 @@ mod tests {
                         .unwrap();
 
                 TestCase {
+                    name: message_name,
                     message: match &project_name {
                         Some(name) => message.with_project_name(name.to_string()),
                         _ => message,
@@ -185,6 +463,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn round_trip() {
+        for test in test_messages() {
+            let json = serde_json::to_string(&test.message).unwrap();
+            let message = serde_json::from_str::<Message>(&json)
+                .map_err(|err| format!("{}: {err}\n{json}", test.name))
+                .unwrap();
+
+            // The project name isn't part of the Pub/Sub message
+            let attributes = Attributes { project_name: None, ..test.message.attributes };
+            assert_eq!(message, Message { attributes, ..test.message }, "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn from_base64() {
+        // "?>?" encodes to "Pz4/" in standard and "Pz4_" in URL-safe base64
+        for data in ["Pz4/", "Pz4_"] {
+            let message = serde_json::from_value::<Message>(serde_json::json!({ "data": data }))
+                .unwrap_or_else(|err| panic!("{data}: {err}"));
+            assert_eq!(message.data, "?>?", "{data}");
+        }
+
+        let err = serde_json::from_value::<Message>(serde_json::json!({ "data": "not base64!" }))
+            .unwrap_err();
+        assert!(err.to_string().contains("base64"), "{err}");
+    }
+
     #[test]
     fn log_entry() {
         for test in test_messages() {
@@ -194,6 +500,99 @@ mod tests {
         }
     }
 
+    #[test]
+    fn is_stale() {
+        let limit = std::time::Duration::from_secs(3600);
+        let message =
+            Message { publish_time: "2023-01-13T19:51:24.884Z".to_string(), ..Default::default() };
+        let publish_time = message.publish_time().unwrap();
+
+        let at = |secs| publish_time + TimeDelta::seconds(secs);
+        assert_eq!(message.age_at(at(3600)), Some(TimeDelta::seconds(3600)));
+        assert!(!message.is_stale_at(limit, at(3599)));
+        assert!(!message.is_stale_at(limit, at(3600)));
+        assert!(message.is_stale_at(limit, at(3601)));
+        assert!(message.is_stale_at(limit, at(3600) + TimeDelta::milliseconds(1)));
+
+        // Messages without a valid publish time are never stale
+        let message = Message { publish_time: "yesterday".to_string(), ..Default::default() };
+        assert_eq!(message.age(), None);
+        assert!(!message.is_stale(std::time::Duration::ZERO));
+    }
+
+    #[test]
+    fn log_entry_localized() {
+        for test in test_messages() {
+            assert_eq!(
+                test.message.log_entry_localized(Locale::En),
+                test.log_entry,
+                "{}",
+                test.name
+            );
+            // The type URL, status message and data come from GKE, so they're English
+            let status_message = match &test.message.attributes.payload {
+                Payload::ClusterStatusChangeEvent(p) => p.status_message.as_str(),
+                _ => "",
+            };
+            for locale in [Locale::De, Locale::Fr, Locale::Ja] {
+                let log_entry = test.message.log_entry_localized(locale);
+                let log_entry = log_entry
+                    .replace(&test.message.attributes.type_url, "")
+                    .replace(status_message, "")
+                    .replace(&test.message.data, "");
+                assert_english_free(&log_entry, &test.name);
+            }
+        }
+    }
+
+    #[test]
+    fn format_duration() {
+        let second = TimeDelta::seconds(1);
+        assert_eq!(super::format_duration(TimeDelta::seconds(42), second), "42s");
+        assert_eq!(super::format_duration(TimeDelta::seconds(2207), second), "36m 47s");
+        assert_eq!(super::format_duration(TimeDelta::seconds(3723), second), "1h 2m 3s");
+        assert_eq!(super::format_duration(TimeDelta::seconds(-5), second), "0s");
+
+        let minute = TimeDelta::minutes(1);
+        assert_eq!(super::format_duration(TimeDelta::seconds(8108), minute), "2h 15m");
+        assert_eq!(super::format_duration(TimeDelta::seconds(30), minute), "30s");
+        assert_eq!(super::format_duration(TimeDelta::days(45), minute), "1month 14days 13h 26m");
+    }
+
+    #[test]
+    fn plain_text_localized() {
+        for test in test_messages() {
+            for locale in [Locale::De, Locale::Fr, Locale::Ja] {
+                let plain_text = test.message.plain_text_localized(locale);
+                println!("{plain_text}");
+                assert_english_free(&plain_text, &test.name);
+            }
+        }
+    }
+
+    /// Asserts that a translated text doesn't contain any of the English
+    /// phrases of the messages.
+    fn assert_english_free(text: &str, name: &str) {
+        for english in [
+            "has been issued",
+            "has new version",
+            "available",
+            "upgrading",
+            "control plane",
+            "node pool",
+            "status changed",
+            "configuration changed",
+            "autoscaled",
+            "was created",
+            "was deleted",
+            "unknown",
+            "invalid",
+            "received event",
+        ] {
+            assert!(!text.to_lowercase().contains(english), "{name}: {english} in {text}");
+        }
+    }
+
     #[test]
     fn plain_text() {
         for test in test_messages() {
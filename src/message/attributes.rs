@@ -1,10 +1,47 @@
 pub mod payload;
 
-use serde::{de, Deserialize};
+use std::fmt::Display;
 
-use self::payload::{Payload, ResourceType};
+use serde::ser::{self, SerializeMap};
+use serde::{de, Deserialize, Serialize, Serializer};
 
-#[derive(Debug, Default)]
+use self::payload::{Payload, ReleaseChannel, ResourceType};
+
+/// The minor versions a node pool can be behind the control plane before
+/// `node_pool_version_skew_check` warns about it.
+const MAX_MINOR_VERSION_SKEW: u64 = 1;
+
+/// The kind of cluster a message is about, detected from the `type_url`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ClusterType {
+    #[default]
+    Standard,
+    #[allow(dead_code)] // GKE doesn't tell Autopilot clusters apart in notifications yet
+    Autopilot,
+
+    /// GKE on Bare Metal, now GKE Distributed (formerly Anthos on Bare Metal).
+    BareMetal,
+
+    /// GKE on VMware (formerly Anthos on VMware).
+    VMware,
+}
+
+impl ClusterType {
+    fn from_type_url(type_url: &str) -> Self {
+        let Some(name) = type_url.strip_prefix("type.googleapis.com/google.container.") else {
+            return ClusterType::Standard;
+        };
+        if name.starts_with("baremetal.v1") {
+            ClusterType::BareMetal
+        } else if name.starts_with("vmware.v1") {
+            ClusterType::VMware
+        } else {
+            ClusterType::Standard
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
 pub struct Attributes {
     pub project_id: String,
     pub project_name: Option<String>, // Unfortunately not included in the pub/sub message (filled from env var)
@@ -12,6 +49,22 @@ pub struct Attributes {
     pub cluster_location: String,
     pub type_url: String,
     pub payload: Payload,
+
+    /// Whether auto-upgrade is enabled for the cluster, if known. GKE doesn't
+    /// currently include this, so it's filled from `AUTO_UPGRADE_CLUSTERS`.
+    pub cluster_auto_upgrade: Option<bool>,
+
+    /// The release channel the cluster is subscribed to, when GKE includes
+    /// it in the `cluster_release_channel` attribute.
+    pub cluster_release_channel: Option<ReleaseChannel>,
+
+    /// The type of the cluster's maintenance policy, e.g. `RECURRING_WINDOW`
+    /// or `NONE`, when included in the `cluster_maintenance_policy` attribute.
+    pub cluster_maintenance_policy: Option<String>,
+
+    /// Detected from the `type_url`, clusters outside of Google Cloud don't
+    /// have the Console pages of GKE clusters.
+    pub cluster_type: ClusterType,
 }
 
 impl Attributes {
@@ -19,54 +72,44 @@ impl Attributes {
         Self { project_name: Some(project_name), ..self }
     }
 
+    pub fn with_cluster_auto_upgrade(self, cluster_auto_upgrade: bool) -> Self {
+        Self { cluster_auto_upgrade: Some(cluster_auto_upgrade), ..self }
+    }
+
+    /// Whether the message is missing its type, payload, cluster name or
+    /// project ID.
     pub fn is_invalid(&self) -> bool {
-        self.type_url.is_empty() || matches!(self.payload, Payload::None)
+        self.type_url.is_empty()
+            || matches!(self.payload, Payload::None)
+            || self.cluster_name.is_empty()
+            || self.project_id.is_empty()
     }
 
-    pub fn log_message(&self) -> Result<String, String> {
-        match &self.payload {
-            Payload::SecurityBulletinEvent(p) => Ok(format!(
-                "Security bulletin {} affecting {} has been issued",
-                p.bulletin_id,
-                self.resource_uri()
-            )),
-            Payload::UpgradeAvailableEvent(p) => match &p.resource_type {
-                ResourceType::ControlPlane => Ok(format!(
-                    "Control plane {} has new version {} available for upgrade in the {} channel",
-                    self.resource_uri(),
-                    p.version,
-                    p.release_channel,
-                )),
-                ResourceType::NodePool => Ok(format!(
-                    "Node pool {} has new version {} available for upgrade in the {} channel",
-                    self.resource_uri(),
-                    p.version,
-                    p.release_channel,
-                )),
-                ResourceType::Unknown(str) => {
-                    Ok(format!("Unknown resource type `{str}` encountered"))
-                }
-            },
-            Payload::UpgradeEvent(p) => match &p.resource_type {
-                ResourceType::ControlPlane => Ok(format!(
-                    "Control plane {} is upgrading from version {} to {}",
-                    self.resource_uri(),
-                    p.current_version,
-                    p.target_version
-                )),
-                ResourceType::NodePool => Ok(format!(
-                    "Node pool {} is upgrading from {} to {}",
-                    self.resource_uri(),
-                    p.current_version,
-                    p.target_version
-                )),
-                ResourceType::Unknown(str) => {
-                    Ok(format!("Unknown resource type `{str}` encountered"))
-                }
-            },
-            _ if self.is_invalid() => Err("Empty or invalid payload".to_string()),
-            _ => Err(format!("Unknown message type `{}` encountered", self.type_url)),
-        }
+    /// The name of the event type, e.g. `UpgradeEvent` for
+    /// `type.googleapis.com/google.container.v1beta1.UpgradeEvent`.
+    pub fn event_type(&self) -> &str {
+        self.type_url.rsplit('.').next().unwrap_or_default()
+    }
+
+    /// Tags for the message in Datadog's `key:value` format.
+    pub fn to_datadog_tags(&self) -> Vec<String> {
+        vec![
+            format!("project:{}", self.project_name()),
+            format!("cluster:{}", self.cluster_name),
+            format!("location:{}", self.cluster_location),
+            format!("event_type:{}", self.event_type()),
+        ]
+    }
+
+    /// The attributes identifying the message as key-value pairs, e.g. for
+    /// logging them as individual fields.
+    pub fn to_log_fields(&self) -> Vec<(&str, String)> {
+        vec![
+            ("project", self.project_name()),
+            ("cluster", self.cluster_name.clone()),
+            ("location", self.cluster_location.clone()),
+            ("type", self.type_url.clone()),
+        ]
     }
 
     pub fn project_name(&self) -> String {
@@ -83,6 +126,16 @@ impl Attributes {
                 ResourceType::NodePool => &p.resource,
                 _ => &None,
             },
+            Payload::NodeUpgradeCompleteEvent(p) => match p.resource_type {
+                ResourceType::NodePool => &p.resource,
+                _ => &None,
+            },
+            Payload::NodePoolAutoscalingEvent(p) => match p.resource_type {
+                ResourceType::NodePool => &p.resource,
+                _ => &None,
+            },
+            Payload::NodePoolCreatedEvent(p) => &p.resource,
+            Payload::NodePoolDeletedEvent(p) => &p.resource,
             _ => &None,
         } {
             resource.clone()
@@ -96,7 +149,31 @@ impl Attributes {
         }
     }
 
+    /// Whether the cluster runs GKE Distributed (formerly Anthos on Bare
+    /// Metal) rather than on Google Cloud.
+    #[allow(dead_code)] // Only `cluster_type` is used for the Console URLs
+    pub fn is_gke_on_bare_metal(&self) -> bool {
+        self.cluster_type == ClusterType::BareMetal
+    }
+
+    /// The Console overview of the fleet's clusters, which is the only page
+    /// listing GKE on Bare Metal and VMware clusters.
+    fn fleet_clusters_url(&self) -> String {
+        format!(
+            "https://console.cloud.google.com/kubernetes/list/overview?project={}",
+            self.project_name(),
+        )
+    }
+
+    fn is_on_premises(&self) -> bool {
+        matches!(self.cluster_type, ClusterType::BareMetal | ClusterType::VMware)
+    }
+
     pub fn resource_url(&self) -> String {
+        if self.is_on_premises() {
+            return self.fleet_clusters_url();
+        }
+
         if let Some(node_pool_name) = match &self.payload {
             Payload::UpgradeAvailableEvent(p) => match p.resource_type {
                 ResourceType::NodePool => p.node_pool_name(),
@@ -106,23 +183,102 @@ impl Attributes {
                 ResourceType::NodePool => p.node_pool_name(),
                 _ => None,
             },
+            Payload::NodeUpgradeCompleteEvent(p) => match p.resource_type {
+                ResourceType::NodePool => p.node_pool_name(),
+                _ => None,
+            },
+            Payload::NodePoolConfigChangeEvent(p) => {
+                Some(p.node_pool_name.clone()).filter(|name| !name.is_empty())
+            }
+            Payload::NodePoolAutoscalingEvent(p) => match p.resource_type {
+                ResourceType::NodePool => p.node_pool_name(),
+                _ => None,
+            },
+            Payload::NodePoolCreatedEvent(p) => p.node_pool_name(),
+            // Deleted node pools no longer have a page, so the cluster is linked instead
             _ => None,
         } {
-            format!(
-                "https://console.cloud.google.com/kubernetes/nodepool/{}/{}/{}?project={}",
-                self.cluster_location,
-                self.cluster_name,
-                node_pool_name,
-                self.project_name(),
-            )
+            self.node_pool_console_url(&node_pool_name)
         } else {
-            format!(
-                "https://console.cloud.google.com/kubernetes/clusters/details/{}/{}?project={}",
-                self.cluster_location,
-                self.cluster_name,
-                self.project_name(),
-            )
+            self.cluster_console_url()
+        }
+    }
+
+    /// The Console page of the cluster, ignoring the cluster type.
+    fn cluster_console_url(&self) -> String {
+        self.cluster_console_page("")
+    }
+
+    /// The Console page of a node pool of the cluster.
+    fn node_pool_console_url(&self, node_pool_name: &str) -> String {
+        self.node_pool_console_page(node_pool_name, "")
+    }
+
+    /// A subpage of the Console page of the cluster, e.g. `/upgrade`.
+    fn cluster_console_page(&self, subpage: &str) -> String {
+        format!(
+            "https://console.cloud.google.com/kubernetes/clusters/details/{}/{}{subpage}?project={}",
+            self.cluster_location,
+            self.cluster_name,
+            self.project_name(),
+        )
+    }
+
+    /// A subpage of the Console page of a node pool, e.g. `/nodes`.
+    fn node_pool_console_page(&self, node_pool_name: &str, subpage: &str) -> String {
+        format!(
+            "https://console.cloud.google.com/kubernetes/nodepool/{}/{}/{}{subpage}?project={}",
+            self.cluster_location,
+            self.cluster_name,
+            node_pool_name,
+            self.project_name(),
+        )
+    }
+
+    pub fn upgrade_url(&self) -> String {
+        if self.is_on_premises() {
+            return self.fleet_clusters_url();
+        }
+
+        self.cluster_console_page("/upgrade")
+    }
+
+    pub fn cluster_recommendation_url(&self) -> String {
+        format!(
+            "https://console.cloud.google.com/home/recommendations?project={}",
+            self.project_name(),
+        )
+    }
+
+    /// The Console page listing the project's quotas, as upgrades can fail when
+    /// e.g. the compute quotas are exhausted.
+    pub fn project_quota_url(&self) -> String {
+        format!("https://console.cloud.google.com/iam-admin/quotas?project={}", self.project_name())
+    }
+
+    /// The Cloud Armor security policies of the project, for investigating
+    /// whether a security bulletin can be mitigated at the load balancer.
+    pub fn cloud_armor_url(&self) -> String {
+        format!(
+            "https://console.cloud.google.com/security/cloud-armor?project={}",
+            self.project_name()
+        )
+    }
+
+    /// The Console page listing the nodes of an upgrading node pool, which
+    /// shows the status of the individual nodes during the upgrade.
+    pub fn node_list_url(&self) -> Option<String> {
+        if self.is_on_premises() {
+            return None;
         }
+
+        let p = self.payload.as_upgrade_event()?;
+        let ResourceType::NodePool = p.resource_type else {
+            return None;
+        };
+
+        p.node_pool_name()
+            .map(|node_pool_name| self.node_pool_console_page(&node_pool_name, "/nodes"))
     }
 
     pub fn is_node_pool_upgrade_available_event(&self) -> bool {
@@ -131,6 +287,73 @@ impl Attributes {
             .map(|p| matches!(p.resource_type, ResourceType::NodePool))
             .unwrap_or_default()
     }
+
+    pub fn is_control_plane_upgrade_available_event(&self) -> bool {
+        self.payload
+            .as_upgrade_available_event()
+            .map(|p| matches!(p.resource_type, ResourceType::ControlPlane))
+            .unwrap_or_default()
+    }
+
+    /// The number of minor versions the version of a node pool
+    /// `UpgradeAvailableEvent` is behind the control plane, when it's more
+    /// than the 1 minor version GKE recommends.
+    pub fn node_pool_version_skew_check(&self, control_plane_version: &str) -> Option<u64> {
+        let p = self.payload.as_upgrade_available_event()?;
+        let ResourceType::NodePool = p.resource_type else {
+            return None;
+        };
+
+        p.minor_versions_behind(control_plane_version).filter(|skew| *skew > MAX_MINOR_VERSION_SKEW)
+    }
+}
+
+/// A compact `key=value` line, e.g. `project=my-project cluster=my-cluster
+/// location=us-central1 type=type.googleapis.com/...`.
+impl Display for Attributes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (key, value)) in self.to_log_fields().iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes to the Pub/Sub message attributes, which are all strings with
+/// the payload as a JSON string. The `project_name` and `cluster_type`
+/// aren't attributes, so they're left out.
+impl Serialize for Attributes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("project_id", &self.project_id)?;
+        map.serialize_entry("cluster_name", &self.cluster_name)?;
+        map.serialize_entry("cluster_location", &self.cluster_location)?;
+        map.serialize_entry("type_url", &self.type_url)?;
+        match &self.payload {
+            Payload::None => {}
+            Payload::UnknownType(payload) => map.serialize_entry("payload", payload)?,
+            payload => {
+                let payload = serde_json::to_string(payload).map_err(ser::Error::custom)?;
+                map.serialize_entry("payload", &payload)?;
+            }
+        }
+        if let Some(cluster_auto_upgrade) = self.cluster_auto_upgrade {
+            map.serialize_entry("cluster_auto_upgrade", &cluster_auto_upgrade.to_string())?;
+        }
+        if let Some(cluster_release_channel) = &self.cluster_release_channel {
+            map.serialize_entry("cluster_release_channel", cluster_release_channel)?;
+        }
+        if let Some(cluster_maintenance_policy) = &self.cluster_maintenance_policy {
+            map.serialize_entry("cluster_maintenance_policy", cluster_maintenance_policy)?;
+        }
+        map.end()
+    }
 }
 
 impl<'de> Deserialize<'de> for Attributes {
@@ -146,6 +369,9 @@ impl<'de> Deserialize<'de> for Attributes {
             ClusterLocation,
             TypeUrl,
             Payload,
+            ClusterAutoUpgrade,
+            ClusterReleaseChannel,
+            ClusterMaintenancePolicy,
         }
 
         struct AttributesVisitor;
@@ -165,6 +391,9 @@ impl<'de> Deserialize<'de> for Attributes {
                 let mut cluster_location = None;
                 let mut type_url = None;
                 let mut payload = None;
+                let mut cluster_auto_upgrade = None;
+                let mut cluster_release_channel = None;
+                let mut cluster_maintenance_policy = None;
 
                 while let Some(field) = map.next_key()? {
                     match field {
@@ -183,6 +412,16 @@ impl<'de> Deserialize<'de> for Attributes {
                         Field::Payload => {
                             payload = Some(map.next_value::<String>()?);
                         }
+                        Field::ClusterAutoUpgrade => {
+                            cluster_auto_upgrade = map.next_value::<String>()?.parse().ok();
+                        }
+                        Field::ClusterReleaseChannel => {
+                            cluster_release_channel = Some(map.next_value::<ReleaseChannel>()?)
+                                .filter(|channel| *channel != ReleaseChannel::Unspecified);
+                        }
+                        Field::ClusterMaintenancePolicy => {
+                            cluster_maintenance_policy = Some(map.next_value::<String>()?);
+                        }
                     }
                 }
 
@@ -193,6 +432,7 @@ impl<'de> Deserialize<'de> for Attributes {
                 let type_url = type_url.unwrap_or_default();
                 let payload = payload.unwrap_or_default();
 
+                let cluster_type = ClusterType::from_type_url(&type_url);
                 let payload = match type_url.as_str() {
                     "type.googleapis.com/google.container.v1beta1.SecurityBulletinEvent" => {
                         Payload::SecurityBulletinEvent(
@@ -209,6 +449,36 @@ impl<'de> Deserialize<'de> for Attributes {
                             serde_json::from_str(&payload).map_err(de::Error::custom)?,
                         )
                     }
+                    "type.googleapis.com/google.container.v1beta1.ClusterStatusChangeEvent" => {
+                        Payload::ClusterStatusChangeEvent(
+                            serde_json::from_str(&payload).map_err(de::Error::custom)?,
+                        )
+                    }
+                    "type.googleapis.com/google.container.v1beta1.NodeUpgradeCompleteEvent" => {
+                        Payload::NodeUpgradeCompleteEvent(
+                            serde_json::from_str(&payload).map_err(de::Error::custom)?,
+                        )
+                    }
+                    "type.googleapis.com/google.container.v1beta1.NodePoolConfigChangeEvent" => {
+                        Payload::NodePoolConfigChangeEvent(
+                            serde_json::from_str(&payload).map_err(de::Error::custom)?,
+                        )
+                    }
+                    "type.googleapis.com/google.container.v1beta1.NodePoolAutoscalingEvent" => {
+                        Payload::NodePoolAutoscalingEvent(
+                            serde_json::from_str(&payload).map_err(de::Error::custom)?,
+                        )
+                    }
+                    "type.googleapis.com/google.container.v1beta1.NodePoolCreatedEvent" => {
+                        Payload::NodePoolCreatedEvent(
+                            serde_json::from_str(&payload).map_err(de::Error::custom)?,
+                        )
+                    }
+                    "type.googleapis.com/google.container.v1beta1.NodePoolDeletedEvent" => {
+                        Payload::NodePoolDeletedEvent(
+                            serde_json::from_str(&payload).map_err(de::Error::custom)?,
+                        )
+                    }
                     _ => {
                         if payload.is_empty() {
                             Payload::None
@@ -225,6 +495,10 @@ impl<'de> Deserialize<'de> for Attributes {
                     cluster_location,
                     type_url,
                     payload,
+                    cluster_auto_upgrade,
+                    cluster_release_channel,
+                    cluster_maintenance_policy,
+                    cluster_type,
                 })
             }
         }
@@ -232,3 +506,175 @@ impl<'de> Deserialize<'de> for Attributes {
         deserializer.deserialize_map(AttributesVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attributes() -> Attributes {
+        Attributes {
+            project_id: "0123456789".to_string(),
+            cluster_name: "test-cluster".to_string(),
+            cluster_location: "us-central1".to_string(),
+            type_url: "type.googleapis.com/google.container.v1beta1.UnknownEvent".to_string(),
+            payload: Payload::UnknownType("{}".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn to_datadog_tags() {
+        let attr = attributes().with_project_name("my-proj".to_string());
+        assert_eq!(
+            attr.to_datadog_tags(),
+            [
+                "project:my-proj",
+                "cluster:test-cluster",
+                "location:us-central1",
+                "event_type:UnknownEvent"
+            ]
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            attributes().to_string(),
+            "project=0123456789 cluster=test-cluster location=us-central1 \
+             type=type.googleapis.com/google.container.v1beta1.UnknownEvent"
+        );
+        assert_eq!(
+            attributes().with_project_name("my-proj".to_string()).to_string(),
+            "project=my-proj cluster=test-cluster location=us-central1 \
+             type=type.googleapis.com/google.container.v1beta1.UnknownEvent"
+        );
+        assert_eq!(Attributes::default().to_string(), "project= cluster= location= type=",);
+    }
+
+    #[test]
+    fn cluster_release_channel() {
+        for (attribute, expected) in [
+            (Some("STABLE"), Some(ReleaseChannel::Stable)),
+            (Some("RAPID"), Some(ReleaseChannel::Rapid)),
+            (Some("UNSPECIFIED"), None),
+            (None, None),
+        ] {
+            let mut attributes = serde_json::json!({
+                "cluster_name": "test-cluster",
+                "project_id": "0123456789",
+                "type_url": "type.googleapis.com/google.container.v1beta1.UnknownEvent",
+                "payload": "{}",
+            });
+            if let Some(attribute) = attribute {
+                attributes["cluster_release_channel"] = attribute.into();
+            }
+
+            let attr: Attributes = serde_json::from_value(attributes).unwrap();
+            assert_eq!(attr.cluster_release_channel, expected, "{attribute:?}");
+        }
+    }
+
+    #[test]
+    fn cluster_type() {
+        for (type_url, expected) in [
+            ("type.googleapis.com/google.container.v1beta1.UpgradeEvent", ClusterType::Standard),
+            (
+                "type.googleapis.com/google.container.baremetal.v1.UpgradeEvent",
+                ClusterType::BareMetal,
+            ),
+            (
+                "type.googleapis.com/google.container.baremetal.v1alpha1.Event",
+                ClusterType::BareMetal,
+            ),
+            ("type.googleapis.com/google.container.vmware.v1.UpgradeEvent", ClusterType::VMware),
+            ("", ClusterType::Standard),
+        ] {
+            let attr: Attributes = serde_json::from_value(serde_json::json!({
+                "cluster_name": "test-cluster",
+                "cluster_location": "us-central1",
+                "project_id": "0123456789",
+                "type_url": type_url,
+                "payload": "{}",
+            }))
+            .unwrap();
+            assert_eq!(attr.cluster_type, expected, "{type_url}");
+            assert_eq!(attr.is_gke_on_bare_metal(), expected == ClusterType::BareMetal);
+
+            if expected == ClusterType::Standard {
+                assert!(attr.resource_url().contains("/clusters/details/us-central1/test-cluster"));
+            } else {
+                let overview =
+                    "https://console.cloud.google.com/kubernetes/list/overview?project=0123456789";
+                assert_eq!(attr.resource_url(), overview, "{type_url}");
+                assert_eq!(attr.upgrade_url(), overview, "{type_url}");
+            }
+        }
+    }
+
+    #[test]
+    fn console_urls() {
+        let attr: Attributes = serde_json::from_value(serde_json::json!({
+            "cluster_name": "test-cluster",
+            "cluster_location": "us-central1",
+            "project_id": "0123456789",
+            "type_url": "type.googleapis.com/google.container.v1beta1.UpgradeEvent",
+            "payload": "{}",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            attr.cluster_console_url(),
+            "https://console.cloud.google.com/kubernetes/clusters/details/us-central1/test-cluster?project=0123456789"
+        );
+        assert_eq!(attr.resource_url(), attr.cluster_console_url());
+        assert_eq!(
+            attr.node_pool_console_url("default-pool"),
+            "https://console.cloud.google.com/kubernetes/nodepool/us-central1/test-cluster/default-pool?project=0123456789"
+        );
+        assert_eq!(
+            attr.upgrade_url(),
+            "https://console.cloud.google.com/kubernetes/clusters/details/us-central1/test-cluster/upgrade?project=0123456789"
+        );
+    }
+
+    #[test]
+    fn node_pool_version_skew_check() {
+        let upgrade_available = |resource_type, version: &str| Attributes {
+            type_url: "type.googleapis.com/google.container.v1beta1.UpgradeAvailableEvent"
+                .to_string(),
+            payload: Payload::UpgradeAvailableEvent(payload::UpgradeAvailableEvent {
+                resource_type,
+                version: version.to_string(),
+                ..Default::default()
+            }),
+            ..attributes()
+        };
+
+        let node_pool = upgrade_available(ResourceType::NodePool, "1.27.3-gke.100");
+        assert_eq!(node_pool.node_pool_version_skew_check("1.25.3-gke.100"), None, "ahead");
+        assert_eq!(node_pool.node_pool_version_skew_check("1.27.3-gke.100"), None);
+        assert_eq!(node_pool.node_pool_version_skew_check("1.28.1-gke.200"), None);
+        assert_eq!(node_pool.node_pool_version_skew_check("1.29.4-gke.300"), Some(2));
+        assert_eq!(node_pool.node_pool_version_skew_check("1.30.0-gke.1"), Some(3));
+        assert_eq!(node_pool.node_pool_version_skew_check("unknown"), None);
+
+        let control_plane = upgrade_available(ResourceType::ControlPlane, "1.27.3-gke.100");
+        assert_eq!(control_plane.node_pool_version_skew_check("1.29.4-gke.300"), None);
+        assert_eq!(attributes().node_pool_version_skew_check("1.29.4-gke.300"), None);
+    }
+
+    #[test]
+    fn is_invalid() {
+        assert!(!attributes().is_invalid());
+
+        let invalid = [
+            ("empty cluster name", Attributes { cluster_name: String::new(), ..attributes() }),
+            ("empty project ID", Attributes { project_id: String::new(), ..attributes() }),
+            ("empty type URL", Attributes { type_url: String::new(), ..attributes() }),
+            ("no payload", Attributes { payload: Payload::None, ..attributes() }),
+        ];
+        for (name, attr) in invalid {
+            assert!(attr.is_invalid(), "{name}");
+        }
+    }
+}
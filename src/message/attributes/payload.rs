@@ -1,13 +1,62 @@
 use std::fmt::Display;
 
-use serde::{de, Deserialize};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use serde::{de, Deserialize, Serialize, Serializer};
+
+use crate::message::format_duration;
+
+/// The end of standard support of GKE minor versions, copied from the GKE
+/// release schedule:
+/// <https://cloud.google.com/kubernetes-engine/docs/release-schedule>.
+/// The table has to be updated by hand as new minor versions are released,
+/// until then their end of life is unknown.
+const END_OF_LIFE: &[(&str, &str)] = &[
+    ("1.21", "2023-01-31"),
+    ("1.22", "2023-04-30"),
+    ("1.23", "2023-07-31"),
+    ("1.24", "2023-10-31"),
+    ("1.25", "2024-02-29"),
+    ("1.26", "2024-06-30"),
+    ("1.27", "2024-09-30"),
+    ("1.28", "2025-02-04"),
+    ("1.29", "2025-03-21"),
+    ("1.30", "2025-09-30"),
+    ("1.31", "2025-12-22"),
+    ("1.32", "2026-02-28"),
+    ("1.33", "2026-06-30"),
+    ("1.34", "2026-10-31"),
+];
+
+/// The maximum number of bytes of an unknown payload included in `Debug`
+/// output, since its contents are unknown and might be sensitive.
+const MAX_UNKNOWN_DEBUG_LEN: usize = 256;
+
+/// The names of the event types with a known payload, as returned by
+/// `Attributes::event_type`.
+pub const EVENT_TYPES: &[&str] = &[
+    "SecurityBulletinEvent",
+    "UpgradeAvailableEvent",
+    "UpgradeEvent",
+    "ClusterStatusChangeEvent",
+    "NodeUpgradeCompleteEvent",
+    "NodePoolConfigChangeEvent",
+    "NodePoolAutoscalingEvent",
+    "NodePoolCreatedEvent",
+    "NodePoolDeletedEvent",
+];
 
 /// An object carrying notification-specific information.
-#[derive(Debug, Default, Deserialize)]
+#[derive(Default, Deserialize, PartialEq)]
 pub enum Payload {
     SecurityBulletinEvent(SecurityBulletinEvent),
     UpgradeAvailableEvent(UpgradeAvailableEvent),
     UpgradeEvent(UpgradeEvent),
+    ClusterStatusChangeEvent(ClusterStatusChangeEvent),
+    NodeUpgradeCompleteEvent(NodeUpgradeCompleteEvent),
+    NodePoolConfigChangeEvent(NodePoolConfigChangeEvent),
+    NodePoolAutoscalingEvent(NodePoolAutoscalingEvent),
+    NodePoolCreatedEvent(NodePoolCreatedEvent),
+    NodePoolDeletedEvent(NodePoolDeletedEvent),
     UnknownType(String),
 
     #[default]
@@ -22,6 +71,132 @@ impl Payload {
             None
         }
     }
+
+    #[cfg(test)]
+    pub fn as_upgrade_available_event_mut(&mut self) -> Option<&mut UpgradeAvailableEvent> {
+        if let Self::UpgradeAvailableEvent(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_security_bulletin_event(&self) -> Option<&SecurityBulletinEvent> {
+        if let Self::SecurityBulletinEvent(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    #[cfg(test)]
+    pub fn as_security_bulletin_event_mut(&mut self) -> Option<&mut SecurityBulletinEvent> {
+        if let Self::SecurityBulletinEvent(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_upgrade_event(&self) -> Option<&UpgradeEvent> {
+        if let Self::UpgradeEvent(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    #[cfg(test)]
+    pub fn as_upgrade_event_mut(&mut self) -> Option<&mut UpgradeEvent> {
+        if let Self::UpgradeEvent(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    #[allow(dead_code)] // Alongside `as_upgrade_available_event`, not used outside of tests yet
+    pub fn as_node_pool_created_event(&self) -> Option<&NodePoolCreatedEvent> {
+        if let Self::NodePoolCreatedEvent(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    #[allow(dead_code)] // Alongside `as_upgrade_available_event`, not used outside of tests yet
+    pub fn as_node_pool_deleted_event(&self) -> Option<&NodePoolDeletedEvent> {
+        if let Self::NodePoolDeletedEvent(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Debug for Payload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Payload::SecurityBulletinEvent(p) => {
+                f.debug_tuple("SecurityBulletinEvent").field(p).finish()
+            }
+            Payload::UpgradeAvailableEvent(p) => {
+                f.debug_tuple("UpgradeAvailableEvent").field(p).finish()
+            }
+            Payload::UpgradeEvent(p) => f.debug_tuple("UpgradeEvent").field(p).finish(),
+            Payload::ClusterStatusChangeEvent(p) => {
+                f.debug_tuple("ClusterStatusChangeEvent").field(p).finish()
+            }
+            Payload::NodeUpgradeCompleteEvent(p) => {
+                f.debug_tuple("NodeUpgradeCompleteEvent").field(p).finish()
+            }
+            Payload::NodePoolConfigChangeEvent(p) => {
+                f.debug_tuple("NodePoolConfigChangeEvent").field(p).finish()
+            }
+            Payload::NodePoolAutoscalingEvent(p) => {
+                f.debug_tuple("NodePoolAutoscalingEvent").field(p).finish()
+            }
+            Payload::NodePoolCreatedEvent(p) => {
+                f.debug_tuple("NodePoolCreatedEvent").field(p).finish()
+            }
+            Payload::NodePoolDeletedEvent(p) => {
+                f.debug_tuple("NodePoolDeletedEvent").field(p).finish()
+            }
+            Payload::UnknownType(raw) if raw.len() > MAX_UNKNOWN_DEBUG_LEN => {
+                let mut end = MAX_UNKNOWN_DEBUG_LEN;
+                while !raw.is_char_boundary(end) {
+                    end -= 1;
+                }
+                let truncated = format!("{}…[truncated]", &raw[..end]);
+                f.debug_tuple("UnknownType").field(&truncated).finish()
+            }
+            Payload::UnknownType(raw) => f.debug_tuple("UnknownType").field(raw).finish(),
+            Payload::None => f.write_str("None"),
+        }
+    }
+}
+
+/// Serializes the event itself, as in the `payload` attribute (see
+/// `Attributes`), and unknown payloads as they were received.
+impl Serialize for Payload {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Payload::SecurityBulletinEvent(p) => p.serialize(serializer),
+            Payload::UpgradeAvailableEvent(p) => p.serialize(serializer),
+            Payload::UpgradeEvent(p) => p.serialize(serializer),
+            Payload::ClusterStatusChangeEvent(p) => p.serialize(serializer),
+            Payload::NodeUpgradeCompleteEvent(p) => p.serialize(serializer),
+            Payload::NodePoolConfigChangeEvent(p) => p.serialize(serializer),
+            Payload::NodePoolAutoscalingEvent(p) => p.serialize(serializer),
+            Payload::NodePoolCreatedEvent(p) => p.serialize(serializer),
+            Payload::NodePoolDeletedEvent(p) => p.serialize(serializer),
+            Payload::UnknownType(payload) => serializer.serialize_str(payload),
+            Payload::None => serializer.serialize_none(),
+        }
+    }
 }
 
 impl Display for Payload {
@@ -30,6 +205,12 @@ impl Display for Payload {
             Payload::SecurityBulletinEvent(_) => stringify!(SecurityBulletinEvent),
             Payload::UpgradeAvailableEvent(_) => stringify!(UpgradeAvailableEvent),
             Payload::UpgradeEvent(_) => stringify!(UpgradeEvent),
+            Payload::ClusterStatusChangeEvent(_) => stringify!(ClusterStatusChangeEvent),
+            Payload::NodeUpgradeCompleteEvent(_) => stringify!(NodeUpgradeCompleteEvent),
+            Payload::NodePoolConfigChangeEvent(_) => stringify!(NodePoolConfigChangeEvent),
+            Payload::NodePoolAutoscalingEvent(_) => stringify!(NodePoolAutoscalingEvent),
+            Payload::NodePoolCreatedEvent(_) => stringify!(NodePoolCreatedEvent),
+            Payload::NodePoolDeletedEvent(_) => stringify!(NodePoolDeletedEvent),
             Payload::UnknownType(_) => stringify!(UnknownType),
             Payload::None => stringify!(None),
         })
@@ -38,12 +219,16 @@ impl Display for Payload {
 
 /// SecurityBulletinEvent is a notification sent to customers when
 /// a security bulletin has been posted that they are vulnerable to.
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(default, rename_all = "camelCase")]
 pub struct SecurityBulletinEvent {
     /// The GKE minor versions affected by this vulnerability.
     pub affected_supported_minors: Vec<String>,
 
+    /// The node image types (e.g. Container-Optimized OS, Ubuntu)
+    /// affected by this vulnerability, if it is limited to some.
+    pub affected_image_types: Vec<String>,
+
     /// A brief description of the bulletin. See the bulletin pointed
     /// to by the bulletin_uri field for an expanded description.
     pub brief_description: String,
@@ -81,7 +266,29 @@ pub struct SecurityBulletinEvent {
     pub suggested_upgrade_target: String,
 }
 
+/// The severity of a `SecurityBulletinEvent`, ordered from the least to the
+/// most severe.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    Unknown,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
 impl SecurityBulletinEvent {
+    /// Parses the `severity` field, e.g. `High`, ignoring its case.
+    pub fn severity(&self) -> Severity {
+        match self.severity.to_ascii_lowercase().as_str() {
+            "low" => Severity::Low,
+            "medium" => Severity::Medium,
+            "high" => Severity::High,
+            "critical" => Severity::Critical,
+            _ => Severity::Unknown,
+        }
+    }
+
     pub fn resource_type_affected(&self) -> String {
         match self.resource_type_affected.as_str() {
             "RESOURCE_TYPE_CONTROLPLANE" => "Control Plane".to_string(),
@@ -90,6 +297,24 @@ impl SecurityBulletinEvent {
         }
     }
 
+    /// The GKE documentation for the affected resource type, the node
+    /// security bulletins for nodes and the general listing otherwise.
+    pub fn documentation_url(&self) -> &'static str {
+        match self.resource_type_affected.as_str() {
+            "RESOURCE_TYPE_NODE" => {
+                "https://cloud.google.com/kubernetes-engine/docs/concepts/node-security-bulletins"
+            }
+            _ => "https://cloud.google.com/kubernetes-engine/docs/resources/security-bulletins",
+        }
+    }
+
+    /// Roughly how many days ago the bulletin was issued, counted from
+    /// January 1 of the year in its ID (e.g. `GCP-2022-005`), or `None` when
+    /// the ID has no year.
+    pub fn age_days(&self) -> Option<i64> {
+        bulletin_age_days(&self.bulletin_id, Utc::now().date_naive())
+    }
+
     pub fn manual_steps_required(&self) -> &str {
         match self.manual_steps_required {
             true => "Yes",
@@ -99,7 +324,7 @@ impl SecurityBulletinEvent {
 }
 
 /// UpgradeAvailableEvent is sent when a new available version is released.
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(default, rename_all = "camelCase")]
 pub struct UpgradeAvailableEvent {
     /// The release channel of the version.
@@ -114,22 +339,103 @@ pub struct UpgradeAvailableEvent {
 
     /// The release version available for upgrade.
     pub version: String,
+
+    /// (Optional) The number of nodes of the node pool.
+    pub node_count: Option<u32>,
+
+    /// (Optional) The number of preemptible nodes of the node pool.
+    pub preemptible_count: Option<u32>,
 }
 
 impl UpgradeAvailableEvent {
-    pub fn node_pool_name(&self) -> Option<String> {
-        if let Some(resource) = &self.resource {
-            if let Some((_, name)) = resource.split_once("nodePools/") {
-                return Some(name.to_string());
+    /// The size of the node pool, e.g. `3 nodes (1 preemptible)`, when the
+    /// event includes its node count.
+    pub fn node_pool_size(&self) -> Option<String> {
+        let nodes = |count: u32| match count {
+            1 => "1 node".to_string(),
+            _ => format!("{count} nodes"),
+        };
+
+        let node_count = self.node_count?;
+        Some(match self.preemptible_count {
+            Some(preemptible_count) => {
+                format!("{} ({preemptible_count} preemptible)", nodes(node_count))
             }
+            None => nodes(node_count),
+        })
+    }
+
+    pub fn node_pool_name(&self) -> Option<String> {
+        node_pool_name(self.resource.as_deref())
+    }
+
+    /// The number of days until the minor version of `current_version`
+    /// reaches its end of life (negative once it has), or `None` when the
+    /// minor version isn't in the embedded table.
+    pub fn current_version_eol_days(current_version: &str) -> Option<i64> {
+        eol_days(current_version, Utc::now().date_naive())
+    }
+
+    /// The GKE version history the available version is listed in, i.e. the
+    /// GKE release notes. GKE has no page per minor version, the release
+    /// notes cover the patch releases (`-gke.N`) of every minor version.
+    pub fn version_history_url(&self) -> String {
+        "https://cloud.google.com/kubernetes-engine/docs/release-notes".to_string()
+    }
+
+    /// Whether the available version patches a security bulletin, i.e. it
+    /// is at or after one of the bulletin's `patched_versions` within the
+    /// same minor version. This is a heuristic, GKE doesn't say which
+    /// bulletins a version patches.
+    pub fn is_security_patch(&self, patched_versions: &[String]) -> bool {
+        let Some(version) = gke_version(&self.version) else {
+            return false;
+        };
+
+        let minor = |(kubernetes, _): &(semver::Version, _)| (kubernetes.major, kubernetes.minor);
+        patched_versions
+            .iter()
+            .filter_map(|patched| gke_version(patched))
+            .any(|patched| minor(&patched) == minor(&version) && version >= patched)
+    }
+
+    /// The number of minor versions the available version is behind
+    /// `other_version`, when both share the major version and it isn't ahead.
+    pub fn minor_versions_behind(&self, other_version: &str) -> Option<u64> {
+        let ((version, _), (other, _)) = (gke_version(&self.version)?, gke_version(other_version)?);
+        match version.major == other.major {
+            true => other.minor.checked_sub(version.minor),
+            false => None,
+        }
+    }
+
+    /// Whether the available version is strictly greater than `current`, e.g.
+    /// `1.28.3-gke.1234` is newer than `1.28.3-gke.1000` and `1.27.8`. The
+    /// `-gke.N` suffixes are compared after the Kubernetes versions. Versions
+    /// which can't be parsed are considered newer, so they're never
+    /// suppressed.
+    pub fn is_newer_than(&self, current: &str) -> bool {
+        match (gke_version(&self.version), gke_version(current)) {
+            (Some(available), Some(current)) => available > current,
+            _ => true,
         }
-        None
     }
 }
 
-/// Indicates which release channel a cluster is subscribed to.
-#[derive(Debug, Default, Deserialize)]
-#[serde(tag = "channel", rename_all = "UPPERCASE")]
+/// Parses a GKE version like `1.28.3-gke.1234` into its Kubernetes version
+/// and GKE build number. Other suffixes are kept as semver pre-releases.
+fn gke_version(version: &str) -> Option<(semver::Version, Option<u64>)> {
+    let (kubernetes, build) = match version.split_once("-gke.") {
+        Some((kubernetes, build)) => (kubernetes, Some(build.parse().ok()?)),
+        None => (version, None),
+    };
+    Some((semver::Version::parse(kubernetes).ok()?, build))
+}
+
+/// Indicates which release channel a cluster is subscribed to. GKE sends
+/// it either as a `{"channel": "RAPID"}` object or as a bare `"RAPID"`
+/// string, unknown channels are `Unspecified`.
+#[derive(Debug, Default, PartialEq)]
 pub enum ReleaseChannel {
     #[default]
     Unspecified,
@@ -149,8 +455,62 @@ impl std::fmt::Display for ReleaseChannel {
     }
 }
 
+impl Serialize for ReleaseChannel {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReleaseChannel {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ReleaseChannelVisitor;
+        impl<'de> de::Visitor<'de> for ReleaseChannelVisitor {
+            type Value = ReleaseChannel;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a release channel or an object with a channel")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(match v {
+                    "RAPID" => ReleaseChannel::Rapid,
+                    "REGULAR" => ReleaseChannel::Regular,
+                    "STABLE" => ReleaseChannel::Stable,
+                    _ => ReleaseChannel::Unspecified,
+                })
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+            where
+                V: de::MapAccess<'de>,
+            {
+                let mut channel = ReleaseChannel::Unspecified;
+                while let Some(key) = map.next_key::<String>()? {
+                    if key == "channel" {
+                        channel = map.next_value::<ReleaseChannel>()?;
+                    } else {
+                        map.next_value::<de::IgnoredAny>()?;
+                    }
+                }
+                Ok(channel)
+            }
+        }
+
+        deserializer.deserialize_any(ReleaseChannelVisitor)
+    }
+}
+
 /// UpgradeEvent is a notification sent when a resource is upgrading.
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(default, rename_all = "camelCase")]
 pub struct UpgradeEvent {
     /// The current version before the upgrade.
@@ -171,20 +531,251 @@ pub struct UpgradeEvent {
 
     /// The target version for the upgrade.
     pub target_version: String,
+
+    /// (Optional) The number of nodes of the upgrading node pool.
+    pub node_count: Option<u32>,
 }
 
 impl UpgradeEvent {
     pub fn node_pool_name(&self) -> Option<String> {
-        if let Some(resource) = &self.resource {
-            if let Some((_, name)) = resource.split_once("nodePools/") {
-                return Some(name.to_string());
-            }
+        node_pool_name(self.resource.as_deref())
+    }
+
+    /// The number of pods evicted when each node of the upgrading node pool
+    /// is drained, estimated from its node count.
+    pub fn estimated_pod_evictions(&self, avg_pods_per_node: u32) -> Option<u32> {
+        match self.resource_type {
+            ResourceType::NodePool => Some(self.node_count?.saturating_mul(avg_pods_per_node)),
+            _ => None,
         }
-        None
     }
+
+    /// Whether the target version is older than the current version, which
+    /// happens when GKE rolls a resource back to an earlier release.
+    pub fn is_rollback(&self) -> bool {
+        match (gke_version(&self.current_version), gke_version(&self.target_version)) {
+            (Some(current), Some(target)) => target < current,
+            _ => false,
+        }
+    }
+
+    /// How long ago the upgrade started, when `operation_start_time` is a
+    /// valid timestamp in the past.
+    pub fn elapsed_time(&self) -> Option<TimeDelta> {
+        self.elapsed_time_at(Utc::now())
+    }
+
+    /// The `elapsed_time` as e.g. `2h 15m`, or `Unknown`.
+    pub fn formatted_elapsed(&self) -> String {
+        self.elapsed_time()
+            .map(|elapsed| format_duration(elapsed, TimeDelta::minutes(1)))
+            .unwrap_or_else(|| "Unknown".to_string())
+    }
+
+    /// The `operation_start_time`, when it is a valid timestamp.
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.operation_start_time).ok().map(|start| start.to_utc())
+    }
+
+    fn elapsed_time_at(&self, now: DateTime<Utc>) -> Option<TimeDelta> {
+        Some(now - self.start_time()?).filter(|elapsed| *elapsed >= TimeDelta::zero())
+    }
+}
+
+/// NodeUpgradeCompleteEvent is a notification sent when a node pool
+/// upgrade has finished successfully.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NodeUpgradeCompleteEvent {
+    /// The version before the upgrade.
+    pub current_version: String,
+
+    /// The version the resource was upgraded to.
+    pub target_version: String,
+
+    /// (Optional) Relative path to the resource, e.g.
+    /// the relative path of the node pool.
+    pub resource: Option<String>,
+
+    /// The resource type that was upgraded.
+    pub resource_type: ResourceType,
+
+    /// The operation associated with this upgrade.
+    pub operation: String,
+
+    /// The time when the operation was started.
+    pub operation_start_time: String,
+
+    /// The time when the operation finished.
+    pub operation_end_time: String,
+}
+
+impl NodeUpgradeCompleteEvent {
+    pub fn node_pool_name(&self) -> Option<String> {
+        node_pool_name(self.resource.as_deref())
+    }
+
+    /// How long the upgrade took, when both operation timestamps are valid.
+    pub fn elapsed(&self) -> Option<TimeDelta> {
+        let start = DateTime::parse_from_rfc3339(&self.operation_start_time).ok()?;
+        let end = DateTime::parse_from_rfc3339(&self.operation_end_time).ok()?;
+
+        Some(end - start).filter(|elapsed| *elapsed >= TimeDelta::zero())
+    }
+}
+
+/// NodePoolConfigChangeEvent is a notification sent when the configuration
+/// of a node pool, e.g. its machine type or disk size, has changed.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NodePoolConfigChangeEvent {
+    /// The name of the node pool.
+    pub node_pool_name: String,
+
+    /// The names of the configuration fields which changed.
+    pub changed_fields: Vec<String>,
+
+    /// The configuration before the change.
+    pub old_config: serde_json::Value,
+
+    /// The configuration after the change.
+    pub new_config: serde_json::Value,
+}
+
+/// NodePoolAutoscalingEvent is a notification sent when the cluster
+/// autoscaler adds nodes to or removes nodes from a node pool.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NodePoolAutoscalingEvent {
+    /// (Optional) Relative path to the resource, e.g.
+    /// the relative path of the node pool.
+    pub resource: Option<String>,
+
+    /// The resource type that was scaled.
+    pub resource_type: ResourceType,
+
+    /// The number of nodes added, or removed when negative.
+    pub node_count_change: i32,
+
+    /// Why the autoscaler scaled the node pool.
+    pub reason: String,
+}
+
+impl NodePoolAutoscalingEvent {
+    pub fn node_pool_name(&self) -> Option<String> {
+        node_pool_name(self.resource.as_deref())
+    }
+}
+
+/// NodePoolCreatedEvent is a notification sent when a node pool has been
+/// added to a cluster.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NodePoolCreatedEvent {
+    /// (Optional) Relative path to the node pool.
+    pub resource: Option<String>,
+
+    /// The name of the cluster the node pool belongs to.
+    pub cluster_name: String,
+
+    /// The location of the cluster the node pool belongs to.
+    pub cluster_location: String,
+}
+
+impl NodePoolCreatedEvent {
+    pub fn node_pool_name(&self) -> Option<String> {
+        node_pool_name(self.resource.as_deref())
+    }
+}
+
+/// NodePoolDeletedEvent is a notification sent when a node pool has been
+/// removed from a cluster.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NodePoolDeletedEvent {
+    /// (Optional) Relative path to the node pool.
+    pub resource: Option<String>,
+
+    /// The name of the cluster the node pool belonged to.
+    pub cluster_name: String,
+
+    /// The location of the cluster the node pool belonged to.
+    pub cluster_location: String,
+}
+
+impl NodePoolDeletedEvent {
+    pub fn node_pool_name(&self) -> Option<String> {
+        node_pool_name(self.resource.as_deref())
+    }
+}
+
+/// The name of the node pool in a relative resource path, e.g. `default-pool`
+/// for `projects/my-project/locations/us-central1/clusters/my-cluster/nodePools/default-pool`.
+fn node_pool_name(resource: Option<&str>) -> Option<String> {
+    let (_, name) = resource?.split_once("nodePools/")?;
+    Some(name.to_string())
+}
+
+fn bulletin_age_days(bulletin_id: &str, today: NaiveDate) -> Option<i64> {
+    let year = bulletin_id.split('-').nth(1)?.parse().ok()?;
+    let issued = NaiveDate::from_yo_opt(year, 1)?;
+
+    Some((today - issued).num_days())
+}
+
+/// The number of days from `today` until the minor version of `version`
+/// reaches its end of life, see `current_version_eol_days`.
+pub fn eol_days(version: &str, today: NaiveDate) -> Option<i64> {
+    let (version, _) = gke_version(version)?;
+    let minor = format!("{}.{}", version.major, version.minor);
+    let (_, end_of_life) = END_OF_LIFE.iter().find(|(eol_minor, _)| *eol_minor == minor)?;
+    let end_of_life = end_of_life.parse::<NaiveDate>().ok()?;
+
+    Some((end_of_life - today).num_days())
+}
+
+/// ClusterStatusChangeEvent is a notification sent when a cluster
+/// transitions to a new status, e.g. from RUNNING to ERROR.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ClusterStatusChangeEvent {
+    /// The status the resource transitioned to.
+    pub status: ClusterStatus,
+
+    /// Additional information about the current status, if any.
+    pub status_message: String,
+
+    /// The resource type whose status changed.
+    pub resource_type: ResourceType,
+}
+
+/// The lifecycle status of a cluster.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ClusterStatus {
+    Running,
+    Error,
+    Degraded,
+    Stopped,
+
+    #[default]
+    #[serde(other)]
+    StatusUnspecified,
 }
 
-#[derive(Debug)]
+impl std::fmt::Display for ClusterStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ClusterStatus::Running => "RUNNING",
+            ClusterStatus::Error => "ERROR",
+            ClusterStatus::Degraded => "DEGRADED",
+            ClusterStatus::Stopped => "STOPPED",
+            ClusterStatus::StatusUnspecified => "STATUS_UNSPECIFIED",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum ResourceType {
     ControlPlane,
     NodePool,
@@ -197,6 +788,44 @@ impl Default for ResourceType {
     }
 }
 
+impl std::fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ResourceType::ControlPlane => "ControlPlane",
+            ResourceType::NodePool => "NodePool",
+            ResourceType::Unknown(resource_type) => resource_type,
+        })
+    }
+}
+
+/// Parses both the `Display` names and the names used by GKE (`MASTER` and
+/// `NODE_POOL`), anything else is an unknown resource type.
+impl std::str::FromStr for ResourceType {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ControlPlane" | "MASTER" => ResourceType::ControlPlane,
+            "NodePool" | "NODE_POOL" => ResourceType::NodePool,
+            _ => ResourceType::Unknown(s.to_string()),
+        })
+    }
+}
+
+/// Serializes to the names used by GKE, which `Display` doesn't use.
+impl Serialize for ResourceType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(match self {
+            ResourceType::ControlPlane => "MASTER",
+            ResourceType::NodePool => "NODE_POOL",
+            ResourceType::Unknown(resource_type) => resource_type,
+        })
+    }
+}
+
 impl<'de> Deserialize<'de> for ResourceType {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -214,14 +843,397 @@ impl<'de> Deserialize<'de> for ResourceType {
             where
                 E: de::Error,
             {
-                Ok(match v {
-                    "MASTER" => ResourceType::ControlPlane,
-                    "NODE_POOL" => ResourceType::NodePool,
-                    _ => ResourceType::Unknown(v.to_string()),
-                })
+                Ok(v.parse().unwrap_or_else(|never| match never {}))
             }
         }
 
         deserializer.deserialize_identifier(ResourceTypeVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_type() {
+        for (resource_type, name) in [
+            (ResourceType::ControlPlane, "ControlPlane"),
+            (ResourceType::NodePool, "NodePool"),
+            (ResourceType::Unknown("ETCD".to_string()), "ETCD"),
+            (ResourceType::default(), "UPGRADE_RESOURCE_TYPE_UNSPECIFIED"),
+        ] {
+            assert_eq!(resource_type.to_string(), name);
+            assert_eq!(name.parse::<ResourceType>(), Ok(resource_type));
+        }
+
+        assert_eq!("MASTER".parse::<ResourceType>(), Ok(ResourceType::ControlPlane));
+        assert_eq!("NODE_POOL".parse::<ResourceType>(), Ok(ResourceType::NodePool));
+        assert_eq!(
+            serde_json::from_str::<ResourceType>(r#""NODE_POOL""#).unwrap(),
+            ResourceType::NodePool
+        );
+    }
+
+    #[test]
+    fn documentation_url() {
+        let event = |resource_type_affected: &str| SecurityBulletinEvent {
+            resource_type_affected: resource_type_affected.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            event("RESOURCE_TYPE_NODE").documentation_url(),
+            "https://cloud.google.com/kubernetes-engine/docs/concepts/node-security-bulletins"
+        );
+        for resource_type_affected in ["RESOURCE_TYPE_CONTROLPLANE", ""] {
+            assert_eq!(
+                event(resource_type_affected).documentation_url(),
+                "https://cloud.google.com/kubernetes-engine/docs/resources/security-bulletins"
+            );
+        }
+    }
+
+    #[test]
+    fn accessors() {
+        let mut security_bulletin = Payload::SecurityBulletinEvent(SecurityBulletinEvent {
+            bulletin_id: "GCP-2024-001".to_string(),
+            ..Default::default()
+        });
+        let mut upgrade_available = Payload::UpgradeAvailableEvent(UpgradeAvailableEvent {
+            version: "1.29.4-gke.1043002".to_string(),
+            ..Default::default()
+        });
+        let mut upgrade = Payload::UpgradeEvent(UpgradeEvent {
+            target_version: "1.29.4-gke.1043002".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            security_bulletin.as_security_bulletin_event().unwrap().bulletin_id,
+            "GCP-2024-001"
+        );
+        assert!(security_bulletin.as_upgrade_available_event().is_none());
+        assert!(security_bulletin.as_upgrade_event().is_none());
+        security_bulletin.as_security_bulletin_event_mut().unwrap().bulletin_id =
+            "GCP-2024-002".to_string();
+        assert_eq!(
+            security_bulletin.as_security_bulletin_event().unwrap().bulletin_id,
+            "GCP-2024-002"
+        );
+        assert!(security_bulletin.as_upgrade_event_mut().is_none());
+
+        assert_eq!(
+            upgrade_available.as_upgrade_available_event().unwrap().version,
+            "1.29.4-gke.1043002"
+        );
+        assert!(upgrade_available.as_security_bulletin_event().is_none());
+        assert!(upgrade_available.as_upgrade_event().is_none());
+        upgrade_available.as_upgrade_available_event_mut().unwrap().version =
+            "1.30.0-gke.1".to_string();
+        assert_eq!(upgrade_available.as_upgrade_available_event().unwrap().version, "1.30.0-gke.1");
+        assert!(upgrade_available.as_security_bulletin_event_mut().is_none());
+
+        assert_eq!(upgrade.as_upgrade_event().unwrap().target_version, "1.29.4-gke.1043002");
+        assert!(upgrade.as_security_bulletin_event().is_none());
+        assert!(upgrade.as_upgrade_available_event().is_none());
+        upgrade.as_upgrade_event_mut().unwrap().target_version = "1.30.0-gke.1".to_string();
+        assert_eq!(upgrade.as_upgrade_event().unwrap().target_version, "1.30.0-gke.1");
+        assert!(upgrade.as_upgrade_available_event_mut().is_none());
+
+        assert!(Payload::None.as_upgrade_event().is_none());
+    }
+
+    #[test]
+    fn release_channel() {
+        for (json, expected) in [
+            (r#"{"channel":"RAPID"}"#, ReleaseChannel::Rapid),
+            (r#"{"channel":"STABLE","other":1}"#, ReleaseChannel::Stable),
+            (r#""REGULAR""#, ReleaseChannel::Regular),
+            (r#""EXTENDED""#, ReleaseChannel::Unspecified),
+            (r#"{"channel":"EXTENDED"}"#, ReleaseChannel::Unspecified),
+            (r#"{}"#, ReleaseChannel::Unspecified),
+        ] {
+            assert_eq!(serde_json::from_str::<ReleaseChannel>(json).unwrap(), expected, "{json}");
+        }
+
+        let event = serde_json::from_str::<UpgradeAvailableEvent>(
+            r#"{"version":"1.29.4-gke.1043002","releaseChannel":"RAPID"}"#,
+        )
+        .unwrap();
+        assert_eq!(event.release_channel, ReleaseChannel::Rapid);
+        assert!(serde_json::from_str::<ReleaseChannel>("1").is_err());
+    }
+
+    fn upgrade_event(current_version: &str, target_version: &str) -> UpgradeEvent {
+        UpgradeEvent {
+            current_version: current_version.to_string(),
+            target_version: target_version.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn minor_versions_behind() {
+        let event =
+            UpgradeAvailableEvent { version: "1.27.3-gke.100".to_string(), ..Default::default() };
+
+        assert_eq!(event.minor_versions_behind("1.27.8-gke.1067004"), Some(0));
+        assert_eq!(event.minor_versions_behind("1.29.4-gke.1043002"), Some(2));
+        assert_eq!(event.minor_versions_behind("1.25.0"), None, "ahead");
+        assert_eq!(event.minor_versions_behind("2.27.0"), None);
+        assert_eq!(event.minor_versions_behind("1"), None);
+        assert_eq!(event.minor_versions_behind(""), None);
+    }
+
+    #[test]
+    fn version_history_url() {
+        let event = |version: &str| UpgradeAvailableEvent {
+            version: version.to_string(),
+            ..Default::default()
+        };
+
+        for version in ["1.28.3-gke.1234", "latest", ""] {
+            assert_eq!(
+                event(version).version_history_url(),
+                "https://cloud.google.com/kubernetes-engine/docs/release-notes"
+            );
+        }
+    }
+
+    #[test]
+    fn is_newer_than() {
+        let event = |version: &str| UpgradeAvailableEvent {
+            version: version.to_string(),
+            ..Default::default()
+        };
+
+        for (available, current, expected) in [
+            ("1.28.3-gke.1234", "1.28.3-gke.1000", true),
+            ("1.28.3-gke.1234", "1.28.3-gke.1234", false),
+            ("1.28.3-gke.1000", "1.28.3-gke.1234", false),
+            ("1.28.3-gke.1234", "1.28.2-gke.9999", true),
+            ("1.29.0-gke.100", "1.28.15-gke.1000", true),
+            ("1.28.3-gke.1234", "1.28.3", true),
+            ("1.28.3", "1.28.3-gke.1234", false),
+            ("1.27.8-gke.1067004", "1.28.3", false),
+            ("1.29.0", "1.29.0-rc.1", true),
+            ("1.29.0-rc.1", "1.29.0", false),
+            ("1.29.0-rc.2", "1.29.0-rc.1", true),
+            ("1.29.0-gke.100", "latest", true),
+            ("", "1.28.3", true),
+        ] {
+            assert_eq!(
+                event(available).is_newer_than(current),
+                expected,
+                "{available} > {current}"
+            );
+        }
+    }
+
+    #[test]
+    fn severity() {
+        for (severity, expected) in [
+            ("CRITICAL", Severity::Critical),
+            ("High", Severity::High),
+            ("medium", Severity::Medium),
+            ("Low", Severity::Low),
+            ("", Severity::Unknown),
+            ("Severe", Severity::Unknown),
+        ] {
+            let bulletin =
+                SecurityBulletinEvent { severity: severity.to_string(), ..Default::default() };
+            assert_eq!(bulletin.severity(), expected, "{severity}");
+        }
+        assert!(Severity::Critical > Severity::High && Severity::Low > Severity::Unknown);
+    }
+
+    #[test]
+    fn is_security_patch() {
+        let patched_versions = ["1.21.9-gke.200".to_string(), "1.22.6-gke.600".to_string()];
+        let event = |version: &str| UpgradeAvailableEvent {
+            version: version.to_string(),
+            ..Default::default()
+        };
+
+        assert!(event("1.22.6-gke.600").is_security_patch(&patched_versions));
+        assert!(event("1.22.7-gke.100").is_security_patch(&patched_versions));
+        assert!(event("1.21.10-gke.100").is_security_patch(&patched_versions));
+        assert!(!event("1.22.6-gke.300").is_security_patch(&patched_versions));
+        assert!(!event("1.23.1-gke.100").is_security_patch(&patched_versions));
+        assert!(!event("").is_security_patch(&patched_versions));
+        assert!(!event("1.22.6-gke.600").is_security_patch(&[]));
+    }
+
+    #[test]
+    fn bulletin_age_days() {
+        let today = NaiveDate::from_ymd_opt(2023, 2, 28).unwrap();
+
+        assert_eq!(super::bulletin_age_days("GKE-2023-0001", today), Some(58));
+        assert_eq!(super::bulletin_age_days("GCP-2022-005", today), Some(423));
+        assert_eq!(super::bulletin_age_days("gcp-2022-008", today), Some(423));
+        assert_eq!(super::bulletin_age_days("GCP-2024-001", today), Some(-307));
+        assert_eq!(super::bulletin_age_days("GCP-latest", today), None);
+        assert_eq!(super::bulletin_age_days("", today), None);
+    }
+
+    #[test]
+    fn eol_days() {
+        let today = NaiveDate::from_ymd_opt(2023, 2, 28).unwrap();
+
+        assert_eq!(super::eol_days("1.22.6-gke.300", today), Some(61));
+        assert_eq!(super::eol_days("1.21.9-gke.200", today), Some(-28));
+        assert_eq!(super::eol_days("1.99.1-gke.100", today), None);
+        assert_eq!(super::eol_days("", today), None);
+
+        // Every date in the table should be valid
+        for (minor, date) in END_OF_LIFE {
+            assert!(date.parse::<NaiveDate>().is_ok(), "{minor}");
+        }
+    }
+
+    #[test]
+    fn elapsed() {
+        let event = |start: &str, end: &str| NodeUpgradeCompleteEvent {
+            operation_start_time: start.to_string(),
+            operation_end_time: end.to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            event("2022-03-03T16:04:21.754874604Z", "2022-03-03T16:41:09.123456789Z")
+                .elapsed()
+                .map(|elapsed| elapsed.num_seconds()),
+            Some(2207)
+        );
+        assert_eq!(event("2022-03-03T16:04:21Z", "").elapsed(), None);
+        assert_eq!(event("2022-03-03T16:41:09Z", "2022-03-03T16:04:21Z").elapsed(), None);
+    }
+
+    #[test]
+    fn node_pool_created_deleted() {
+        let resource =
+            "projects/test-project/locations/us-central1/clusters/test-cluster/nodePools/pool-2";
+        let created = Payload::NodePoolCreatedEvent(NodePoolCreatedEvent {
+            resource: Some(resource.to_string()),
+            ..Default::default()
+        });
+        let deleted = Payload::NodePoolDeletedEvent(NodePoolDeletedEvent {
+            resource: Some(resource.to_string()),
+            ..Default::default()
+        });
+
+        let p = created.as_node_pool_created_event().unwrap();
+        assert_eq!(p.node_pool_name().as_deref(), Some("pool-2"));
+        assert!(created.as_node_pool_deleted_event().is_none());
+        assert_eq!(created.to_string(), "NodePoolCreatedEvent");
+
+        let p = deleted.as_node_pool_deleted_event().unwrap();
+        assert_eq!(p.node_pool_name().as_deref(), Some("pool-2"));
+        assert!(deleted.as_node_pool_created_event().is_none());
+        assert_eq!(deleted.to_string(), "NodePoolDeletedEvent");
+
+        assert_eq!(NodePoolDeletedEvent::default().node_pool_name(), None);
+    }
+
+    #[test]
+    fn node_pool_size() {
+        let event = |json: &str| serde_json::from_str::<UpgradeAvailableEvent>(json).unwrap();
+
+        assert_eq!(event(r#"{"nodeCount":3}"#).node_pool_size().unwrap(), "3 nodes");
+        assert_eq!(event(r#"{"nodeCount":1}"#).node_pool_size().unwrap(), "1 node");
+        assert_eq!(
+            event(r#"{"nodeCount":3,"preemptibleCount":1}"#).node_pool_size().unwrap(),
+            "3 nodes (1 preemptible)"
+        );
+        assert_eq!(event(r#"{"preemptibleCount":1}"#).node_pool_size(), None);
+        assert_eq!(event("{}").node_pool_size(), None);
+    }
+
+    #[test]
+    fn estimated_pod_evictions() {
+        let event = |json: &str| serde_json::from_str::<UpgradeEvent>(json).unwrap();
+
+        let node_pool = event(r#"{"resourceType":"NODE_POOL","nodeCount":3}"#);
+        assert_eq!(node_pool.estimated_pod_evictions(10), Some(30));
+        assert_eq!(node_pool.estimated_pod_evictions(0), Some(0));
+        assert_eq!(event(r#"{"resourceType":"NODE_POOL"}"#).estimated_pod_evictions(10), None);
+        assert_eq!(
+            event(r#"{"resourceType":"MASTER","nodeCount":3}"#).estimated_pod_evictions(10),
+            None
+        );
+    }
+
+    #[test]
+    fn elapsed_time() {
+        let now = "2022-03-03T18:19:30Z".parse::<DateTime<Utc>>().unwrap();
+        let event = |start: &str| UpgradeEvent {
+            operation_start_time: start.to_string(),
+            ..Default::default()
+        };
+
+        let started = event("2022-03-03T16:04:21.754874604Z");
+        assert_eq!(started.elapsed_time_at(now).map(|e| e.num_seconds()), Some(8108));
+        let formatted = |start| {
+            let elapsed = event(start).elapsed_time_at(now).unwrap();
+            format_duration(elapsed, TimeDelta::minutes(1))
+        };
+        assert_eq!(formatted("2022-03-03T16:04:21.754874604Z"), "2h 15m");
+        assert_eq!(formatted("2022-03-03T18:02:00Z"), "17m");
+        assert_eq!(formatted("2022-03-03T18:19:00Z"), "30s");
+
+        assert_eq!(event("2022-03-03T18:20:00Z").elapsed_time_at(now), None, "in the future");
+        assert_eq!(event("yesterday").elapsed_time_at(now), None, "malformed");
+        assert_eq!(event("").elapsed_time_at(now), None, "missing");
+        assert_eq!(event("").formatted_elapsed(), "Unknown");
+        assert!(started.elapsed_time().is_some());
+    }
+
+    #[test]
+    fn debug_unknown_type() {
+        let short = Payload::UnknownType("{\"someField\":\"some value\"}".to_string());
+        assert_eq!(format!("{short:?}"), r#"UnknownType("{\"someField\":\"some value\"}")"#);
+
+        let exact = Payload::UnknownType("a".repeat(256));
+        assert_eq!(format!("{exact:?}"), format!("UnknownType(\"{}\")", "a".repeat(256)));
+
+        let long = Payload::UnknownType("a".repeat(257));
+        assert_eq!(
+            format!("{long:?}"),
+            format!("UnknownType(\"{}…[truncated]\")", "a".repeat(256))
+        );
+
+        // Truncation shouldn't split a multi-byte character
+        let multi_byte = Payload::UnknownType(format!("a{}", "é".repeat(200)));
+        assert_eq!(
+            format!("{multi_byte:?}"),
+            format!("UnknownType(\"a{}…[truncated]\")", "é".repeat(127))
+        );
+    }
+
+    #[test]
+    fn debug_known_type() {
+        let payload = Payload::UpgradeEvent(upgrade_event("1.22.4-gke.1501", "1.22.6-gke.300"));
+        assert!(format!("{payload:?}").starts_with("UpgradeEvent(UpgradeEvent { current_version"));
+        assert_eq!(format!("{:?}", Payload::None), "None");
+    }
+
+    #[test]
+    fn is_rollback() {
+        for (current, target, expected) in [
+            ("1.22.4-gke.1501", "1.22.6-gke.300", false),
+            ("1.22.6-gke.300", "1.22.4-gke.1501", true),
+            ("1.22.6-gke.300", "1.22.6-gke.200", true),
+            ("1.22.6-gke.300", "1.22.6-gke.300", false),
+            ("1.23.0-gke.100", "1.22.17-gke.900", true),
+            ("", "1.22.6-gke.300", false),
+            ("1.22.6-gke.300", "", false),
+        ] {
+            assert_eq!(
+                upgrade_event(current, target).is_rollback(),
+                expected,
+                "{current} -> {target}"
+            );
+        }
+    }
+}
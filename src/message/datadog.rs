@@ -0,0 +1,146 @@
+use reqwest::StatusCode;
+use serde::Serialize;
+
+use super::{
+    attributes::payload::{ClusterStatus, Payload},
+    Message,
+};
+use crate::config::Config;
+use crate::error::AppError;
+
+/// An event posted to the Datadog Events API, see
+/// <https://docs.datadoghq.com/api/latest/events/#post-an-event>.
+#[derive(Debug, Serialize)]
+pub struct DatadogEvent {
+    title: String,
+    text: String,
+    alert_type: &'static str,
+    source_type_name: &'static str,
+    aggregation_key: String,
+    tags: Vec<String>,
+}
+
+impl DatadogEvent {
+    pub fn new(message: &Message, config: &Config) -> Self {
+        let attr = &message.attributes;
+
+        let mut tags = attr.to_datadog_tags();
+        if let Some(env) = &config.datadog_env {
+            tags.insert(0, format!("env:{env}"));
+        }
+
+        DatadogEvent {
+            title: message.plain_text(),
+            text: format!(
+                "%%% \n{}\n\n[View in Console]({})\n %%%",
+                message.markdown(),
+                attr.resource_url()
+            ),
+            alert_type: DatadogEvent::alert_type(message),
+            source_type_name: "GKE",
+            aggregation_key: attr.resource_uri(),
+            tags,
+        }
+    }
+
+    /// Posts the event to the Events API of the Datadog site in `api_url`.
+    pub async fn post(
+        &self,
+        client: &reqwest::Client,
+        api_url: &str,
+        api_key: &str,
+    ) -> Result<String, AppError> {
+        let body = serde_json::to_string(self)?;
+        let resp = client
+            .post(format!("{api_url}/api/v1/events"))
+            .header("DD-API-KEY", api_key)
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let text = resp.text().await?;
+
+        match status {
+            StatusCode::OK | StatusCode::ACCEPTED => Ok(text),
+            _ => Err(AppError::HttpPostFailed { status: status.as_u16(), body: text }),
+        }
+    }
+
+    fn alert_type(message: &Message) -> &'static str {
+        match &message.attributes.payload {
+            Payload::SecurityBulletinEvent(_) => "error",
+            Payload::ClusterStatusChangeEvent(p) => match p.status {
+                ClusterStatus::Error => "error",
+                ClusterStatus::Degraded => "warning",
+                _ => "info",
+            },
+            Payload::NodeUpgradeCompleteEvent(_) => "success",
+            _ => "info",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::tests::test_messages;
+    use serde_json::{json, Value};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn alert_type() {
+        for test in test_messages() {
+            let event = DatadogEvent::new(&test.message, &Config::default());
+            let expected = match test.name.as_str() {
+                name if name.starts_with("SecurityBulletinEvent") => "error",
+                "ClusterStatusChangeEvent:Error" => "error",
+                "ClusterStatusChangeEvent:Degraded" => "warning",
+                name if name.starts_with("NodeUpgradeCompleteEvent") => "success",
+                _ => "info",
+            };
+            assert_eq!(event.alert_type, expected, "{}", test.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn post() {
+        let test = test_messages()
+            .into_iter()
+            .find(|test| test.name == "UpgradeEvent:ControlPlane")
+            .unwrap();
+        let config = Config { datadog_env: Some("prod".to_string()), ..Default::default() };
+        let event = DatadogEvent::new(&test.message, &config);
+
+        let datadog = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/events"))
+            .and(header("DD-API-KEY", "test-api-key"))
+            .respond_with(ResponseTemplate::new(202).set_body_json(json!({ "status": "ok" })))
+            .expect(1)
+            .mount(&datadog)
+            .await;
+
+        event.post(&reqwest::Client::new(), &datadog.uri(), "test-api-key").await.unwrap();
+
+        let requests = datadog.received_requests().await.unwrap();
+        let body = serde_json::from_slice::<Value>(&requests[0].body).unwrap();
+        assert_eq!(
+            body["title"],
+            "test-cluster control plane is upgrading to version 1.22.6-gke.300"
+        );
+        assert_eq!(body["alert_type"], "info");
+        assert_eq!(
+            body["tags"],
+            json!([
+                "env:prod",
+                "project:0123456789",
+                "cluster:test-cluster",
+                "location:us-central1",
+                "event_type:UpgradeEvent"
+            ])
+        );
+    }
+}
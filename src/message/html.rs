@@ -0,0 +1,185 @@
+use std::fmt::Write;
+
+use super::{attributes::payload::Payload, Message};
+
+/// A message formatted as the HTML body of an email.
+pub struct HtmlMessage;
+
+impl HtmlMessage {
+    /// The subject of the email, the same text as posted to Slack without
+    /// templates.
+    pub fn subject(message: &Message) -> String {
+        message.plain_text()
+    }
+
+    pub fn render(message: &Message) -> String {
+        let attr = &message.attributes;
+        let mut list = DescriptionList::default();
+        list.text("Cluster", &attr.cluster_name);
+        list.text("Location", &attr.cluster_location);
+        list.text("Project", &attr.project_name());
+
+        match &attr.payload {
+            Payload::SecurityBulletinEvent(p) => {
+                list.html("Bulletin", &link(&p.bulletin_uri, &p.bulletin_id));
+                list.text("Severity", &p.severity);
+                list.text("Description", &p.brief_description);
+                let cves: Vec<_> = p
+                    .cve_ids
+                    .iter()
+                    .map(|id| link(&format!("https://www.cve.org/CVERecord?id={id}"), id))
+                    .collect();
+                list.html("CVEs", &cves.join(", "));
+                list.text("Patched Versions", &p.patched_versions.join(", "));
+                list.text("Suggested Upgrade Target", &p.suggested_upgrade_target);
+                list.text("Manual Steps Required", p.manual_steps_required());
+            }
+            Payload::UpgradeAvailableEvent(p) => {
+                list.text("Version", &p.version);
+                list.text("Release Channel", &p.release_channel.to_string());
+                if let Some(node_pool_name) = p.node_pool_name() {
+                    list.text("Node Pool", &node_pool_name);
+                }
+            }
+            Payload::UpgradeEvent(p) => {
+                list.text("Current Version", &p.current_version);
+                list.text("Target Version", &p.target_version);
+                if let Some(node_pool_name) = p.node_pool_name() {
+                    list.text("Node Pool", &node_pool_name);
+                }
+                list.text("Started", &p.operation_start_time);
+            }
+            Payload::ClusterStatusChangeEvent(p) => {
+                list.text("Status", &p.status.to_string());
+                if !p.status_message.is_empty() {
+                    list.text("Status Message", &p.status_message);
+                }
+            }
+            Payload::NodeUpgradeCompleteEvent(p) => {
+                list.text("Current Version", &p.current_version);
+                list.text("Target Version", &p.target_version);
+                if let Some(node_pool_name) = p.node_pool_name() {
+                    list.text("Node Pool", &node_pool_name);
+                }
+                list.text("Started", &p.operation_start_time);
+                list.text("Finished", &p.operation_end_time);
+            }
+            Payload::NodePoolConfigChangeEvent(p) => {
+                list.text("Node Pool", &p.node_pool_name);
+                list.text("Changed Fields", &p.changed_fields.join(", "));
+            }
+            Payload::NodePoolAutoscalingEvent(p) => {
+                list.text("Node Pool", &p.node_pool_name().unwrap_or_default());
+                list.text("Node Count Change", &format!("{:+}", p.node_count_change));
+                list.text("Reason", &p.reason);
+            }
+            Payload::NodePoolCreatedEvent(p) => {
+                list.text("Created Node Pool", &p.node_pool_name().unwrap_or_default());
+            }
+            Payload::NodePoolDeletedEvent(p) => {
+                list.text("Deleted Node Pool", &p.node_pool_name().unwrap_or_default());
+            }
+            Payload::UnknownType(_) | Payload::None => {}
+        }
+
+        format!(
+            "<html><body><h2>{}</h2><dl>{}</dl><p>{}</p></body></html>",
+            escape(&message.plain_text()),
+            list.0,
+            link(&attr.resource_url(), "View in Console")
+        )
+    }
+}
+
+/// The `<dt>`/`<dd>` pairs of a `<dl>` element.
+#[derive(Default)]
+struct DescriptionList(String);
+
+impl DescriptionList {
+    fn text(&mut self, term: &str, description: &str) {
+        self.html(term, &escape(description));
+    }
+
+    fn html(&mut self, term: &str, description: &str) {
+        let _ = write!(self.0, "<dt>{}</dt><dd>{description}</dd>", escape(term));
+    }
+}
+
+fn link(href: &str, text: &str) -> String {
+    format!(r#"<a href="{}">{}</a>"#, escape(href), escape(text))
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::tests::test_messages;
+
+    #[test]
+    fn render() {
+        for test in test_messages() {
+            let html = HtmlMessage::render(&test.message);
+            let attr = &test.message.attributes;
+
+            assert!(html.starts_with("<html><body><h2>"), "{}", test.name);
+            assert!(
+                html.contains(&format!("<h2>{}</h2>", escape(&test.message.plain_text()))),
+                "{}",
+                test.name
+            );
+            assert!(html.contains(&link(&attr.resource_url(), "View in Console")), "{}", test.name);
+
+            let expected = match &attr.payload {
+                Payload::SecurityBulletinEvent(_) => "<dt>Severity</dt>",
+                Payload::UpgradeAvailableEvent(_) => "<dt>Release Channel</dt>",
+                Payload::UpgradeEvent(_) => "<dt>Target Version</dt>",
+                Payload::ClusterStatusChangeEvent(_) => "<dt>Status</dt>",
+                Payload::NodeUpgradeCompleteEvent(_) => "<dt>Finished</dt>",
+                Payload::NodePoolConfigChangeEvent(_) => "<dt>Changed Fields</dt>",
+                Payload::NodePoolAutoscalingEvent(_) => "<dt>Node Count Change</dt>",
+                Payload::NodePoolCreatedEvent(_) => "<dt>Created Node Pool</dt>",
+                Payload::NodePoolDeletedEvent(_) => "<dt>Deleted Node Pool</dt>",
+                Payload::UnknownType(_) | Payload::None => "<dl><dt>Cluster</dt>",
+            };
+            assert!(html.contains(expected), "{}: {html}", test.name);
+        }
+    }
+
+    #[test]
+    fn security_bulletin_links() {
+        let test = test_messages()
+            .into_iter()
+            .find(|test| test.name == "SecurityBulletinEvent:ControlPlane")
+            .unwrap();
+        let html = HtmlMessage::render(&test.message);
+
+        assert!(html.contains(
+            r#"<dd><a href="https://cloud.google.com/kubernetes-engine/docs/security-bulletins#gcp-2022-005">GCP-2022-005</a></dd>"#
+        ));
+        assert!(html.contains(
+            r#"<dd><a href="https://www.cve.org/CVERecord?id=CVE-2021-43527">CVE-2021-43527</a></dd>"#
+        ));
+    }
+
+    #[test]
+    fn escape_html() {
+        assert_eq!(
+            escape(r#"<script>alert("x & 'y'")</script>"#),
+            "&lt;script&gt;alert(&quot;x &amp; &#39;y&#39;&quot;)&lt;/script&gt;"
+        );
+    }
+}
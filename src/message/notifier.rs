@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::{
+    slack::{Annotations, WebhookMessage},
+    Message,
+};
+use crate::config::Config;
+use crate::error::AppError;
+
+/// Where a `Notifier` posts its message.
+#[derive(Clone, Debug)]
+pub struct NotifierConfig {
+    pub url: String,
+
+    /// Additional headers sent with the message.
+    pub headers: HashMap<String, String>,
+
+    /// The `Content-Type` header, when the service requires one.
+    pub content_type: Option<String>,
+
+    /// When set, posting fails if it doesn't complete in time.
+    pub timeout: Option<Duration>,
+}
+
+impl NotifierConfig {
+    pub fn new(url: String) -> Self {
+        Self { url, headers: HashMap::new(), content_type: None, timeout: None }
+    }
+}
+
+/// A message formatted for a notification service, which can be posted to
+/// it. Implemented by the message type of each service.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Formats the message for the service.
+    fn build(message: &Message, config: &Config, annotations: &Annotations) -> Self
+    where
+        Self: Sized;
+
+    /// Posts the message with `client`, returning the response.
+    async fn post(
+        &self,
+        client: &reqwest::Client,
+        config: &NotifierConfig,
+    ) -> Result<String, AppError>;
+}
+
+#[async_trait]
+impl Notifier for WebhookMessage {
+    fn build(message: &Message, config: &Config, annotations: &Annotations) -> Self {
+        WebhookMessage::new(message, config, annotations)
+    }
+
+    async fn post(
+        &self,
+        client: &reqwest::Client,
+        config: &NotifierConfig,
+    ) -> Result<String, AppError> {
+        self.post_to_webhook(client, config).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::tests::{capture_logs, test_messages};
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn webhook_message(message: &Message) -> WebhookMessage {
+        WebhookMessage::new(message, &Config::default(), &Annotations::default())
+    }
+
+    #[tokio::test]
+    async fn trait_objects() {
+        let slack = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&slack)
+            .await;
+
+        let notifiers: Vec<Box<dyn Notifier>> = test_messages()
+            .iter()
+            .filter(|test| !test.message.is_invalid())
+            .map(|test| Box::new(webhook_message(&test.message)) as Box<dyn Notifier>)
+            .collect();
+
+        let config = NotifierConfig::new(slack.uri());
+        for notifier in &notifiers {
+            assert_eq!(notifier.post(&reqwest::Client::new(), &config).await.unwrap(), "ok");
+        }
+        assert_eq!(slack.received_requests().await.unwrap().len(), notifiers.len());
+    }
+
+    #[tokio::test]
+    async fn headers() {
+        let webhook = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("X-Api-Key", "secret"))
+            .and(header("Content-Type", "application/json"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&webhook)
+            .await;
+
+        let test = test_messages().into_iter().find(|test| !test.message.is_invalid()).unwrap();
+        let config = NotifierConfig {
+            headers: HashMap::from([("X-Api-Key".to_string(), "secret".to_string())]),
+            content_type: Some("application/json".to_string()),
+            ..NotifierConfig::new(webhook.uri())
+        };
+        webhook_message(&test.message)
+            .post_to_webhook(&reqwest::Client::new(), &config)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn unconfirmed_delivery() {
+        let webhook = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("invalid_payload"))
+            .mount(&webhook)
+            .await;
+
+        let (logs, _guard) = capture_logs();
+        let test = test_messages().into_iter().find(|test| !test.message.is_invalid()).unwrap();
+        let notifier: Box<dyn Notifier> = Box::new(webhook_message(&test.message));
+        let response = notifier
+            .post(&reqwest::Client::new(), &NotifierConfig::new(webhook.uri()))
+            .await
+            .unwrap();
+
+        assert_eq!(response, "invalid_payload");
+        assert!(logs.contents().contains("responded with 200 but not ok"), "{}", logs.contents());
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        let slack = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&slack)
+            .await;
+
+        let test = test_messages().into_iter().find(|test| !test.message.is_invalid()).unwrap();
+        let notifier: Box<dyn Notifier> = Box::new(webhook_message(&test.message));
+        let config = NotifierConfig {
+            timeout: Some(Duration::from_millis(50)),
+            ..NotifierConfig::new(slack.uri())
+        };
+
+        match notifier.post(&reqwest::Client::new(), &config).await {
+            Err(AppError::RequestFailed(err)) => assert!(err.is_timeout()),
+            res => panic!("expected AppError::RequestFailed, got {res:?}"),
+        }
+    }
+}
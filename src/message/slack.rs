@@ -1,48 +1,337 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
 use reqwest::StatusCode;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use tracing::warn;
+
+use super::{
+    attributes::payload::{ClusterStatus, Payload, ReleaseChannel, UpgradeAvailableEvent},
+    format_duration,
+    notifier::NotifierConfig,
+    Message,
+};
+use crate::bulletins::SeverityTrend;
+use crate::config::{Config, MaintenanceExclusion};
+use crate::error::AppError;
+use crate::versions::VersionSkew;
+
+/// `UpgradeAvailableEvent` messages warn about the end of life of the minor
+/// version when it's at most this many days away.
+const EOL_WARNING_DAYS: i64 = 90;
+
+/// How long versions typically take to be promoted from the RAPID to the
+/// REGULAR release channel.
+const RAPID_TO_REGULAR_WEEKS: u32 = 2;
+
+/// How long it typically takes for patched versions of a security bulletin
+/// to become available for manual upgrades.
+const MANUAL_PATCHING_DAYS: i64 = 7;
+
+/// Limits of the Block Kit payloads accepted by Slack, see
+/// <https://api.slack.com/reference/block-kit/blocks>.
+const MAX_BLOCKS: usize = 50;
+const MAX_PAYLOAD_SIZE: usize = 1024 * 1024;
+const MAX_MRKDWN_TEXT_LENGTH: usize = 3000;
+
+/// A Block Kit limit exceeded by a `WebhookMessage`.
+#[derive(Debug, PartialEq)]
+pub enum ValidationError {
+    TooManyBlocks(usize),
+    PayloadTooLarge(usize),
+    TextTooLong(usize),
+}
 
-use super::{attributes::payload::Payload, Message};
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::TooManyBlocks(count) => {
+                write!(f, "{count} blocks, at most {MAX_BLOCKS} are allowed")
+            }
+            ValidationError::PayloadTooLarge(size) => {
+                write!(f, "payload is {size} bytes, at most {MAX_PAYLOAD_SIZE} are allowed")
+            }
+            ValidationError::TextTooLong(length) => write!(
+                f,
+                "mrkdwn text is {length} characters, at most {MAX_MRKDWN_TEXT_LENGTH} are allowed"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
 
 #[derive(Debug, Serialize)]
 pub struct WebhookMessage {
+    /// Only used with `chat.postMessage`, Incoming Webhooks always post to
+    /// the channel they were created for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel: Option<String>,
+
+    /// Only used with `chat.postMessage`, posts the message as a reply.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_ts: Option<String>,
+
     text: String,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     blocks: Vec<Value>,
+
+    /// Legacy attachments, used when the blocks should be displayed with a
+    /// colored bar (e.g. for cluster status changes).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<SlackMetadata>,
+}
+
+/// Structured event data attached to a Slack message, see
+/// <https://api.slack.com/metadata/using>.
+#[derive(Debug, Serialize)]
+pub struct SlackMetadata {
+    event_type: String,
+    event_payload: HashMap<String, Value>,
+}
+
+impl From<&Message> for SlackMetadata {
+    fn from(message: &Message) -> Self {
+        let attr = &message.attributes;
+        let event_type = match &attr.payload {
+            Payload::SecurityBulletinEvent(_) => "gke_security_bulletin",
+            Payload::UpgradeAvailableEvent(_) => "gke_upgrade_available",
+            Payload::UpgradeEvent(_) => "gke_upgrade",
+            Payload::ClusterStatusChangeEvent(_) => "gke_cluster_status_change",
+            Payload::NodeUpgradeCompleteEvent(_) => "gke_node_upgrade_complete",
+            Payload::NodePoolConfigChangeEvent(_) => "gke_node_pool_config_change",
+            Payload::NodePoolAutoscalingEvent(_) => "gke_node_pool_autoscaling",
+            Payload::NodePoolCreatedEvent(_) => "gke_node_pool_created",
+            Payload::NodePoolDeletedEvent(_) => "gke_node_pool_deleted",
+            _ => "gke_unknown_event",
+        };
+
+        SlackMetadata {
+            event_type: event_type.to_string(),
+            event_payload: HashMap::from([
+                ("cluster_name".to_string(), json!(attr.cluster_name)),
+                ("project_id".to_string(), json!(attr.project_id)),
+                ("payload_type".to_string(), json!(attr.payload.to_string())),
+            ]),
+        }
+    }
 }
 
 impl WebhookMessage {
-    pub async fn post(&self, webhook: String) -> Result<String, String> {
-        let body = serde_json::to_string(self).map_err(|e| e.to_string())?;
-        let resp = reqwest::Client::new()
-            .post(webhook)
+    /// Slack Incoming Webhooks respond with `ok` once the message is
+    /// delivered. Any other response, even with status 200, hints at a
+    /// misconfigured webhook, so it's logged.
+    fn confirm_delivery(&self, text: String) -> String {
+        if text != "ok" {
+            warn!(response = text, "slack webhook responded with 200 but not ok");
+        }
+        text
+    }
+
+    /// Posts the message to the webhook in `config`, with its headers and
+    /// timeout, logging responses other than Slack's `ok`.
+    pub(super) async fn post_to_webhook(
+        &self,
+        client: &reqwest::Client,
+        config: &NotifierConfig,
+    ) -> Result<String, AppError> {
+        let body = serde_json::to_string(self)?;
+        let mut request = client.post(&config.url).body(body);
+        for (name, value) in &config.headers {
+            request = request.header(name, value);
+        }
+        if let Some(content_type) = &config.content_type {
+            request = request.header("Content-Type", content_type);
+        }
+        if let Some(timeout) = config.timeout {
+            request = request.timeout(timeout);
+        }
+        let resp = request.send().await?;
+
+        let status = resp.status();
+        let text = resp.text().await?;
+
+        match status {
+            StatusCode::OK => Ok(self.confirm_delivery(text)),
+            _ => Err(AppError::HttpPostFailed { status: status.as_u16(), body: text }),
+        }
+    }
+
+    /// Posts the message to `channel` using the `chat.postMessage` Web API,
+    /// as a reply to `thread_ts` when set. Returns the `ts` of the message
+    /// so later messages can be posted in its thread.
+    pub async fn post_message(
+        &mut self,
+        client: &reqwest::Client,
+        api_url: &str,
+        token: &str,
+        channel: &str,
+        thread_ts: Option<String>,
+    ) -> Result<String, AppError> {
+        #[derive(Deserialize)]
+        struct PostMessageResponse {
+            ok: bool,
+            ts: Option<String>,
+            error: Option<String>,
+        }
+
+        self.channel = Some(channel.to_string());
+        self.thread_ts = thread_ts;
+
+        let body = serde_json::to_string(self)?;
+
+        let resp = client
+            .post(format!("{api_url}/chat.postMessage"))
+            .bearer_auth(token)
+            .header("Content-Type", "application/json; charset=utf-8")
             .body(body)
             .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         let status = resp.status();
-        let text = resp.text().await.map_err(|e| e.to_string())?;
+        let text = resp.text().await?;
+        if status != StatusCode::OK {
+            return Err(AppError::HttpPostFailed { status: status.as_u16(), body: text });
+        }
 
-        match status {
-            StatusCode::OK => Ok(text),
-            _ => Err(text),
+        match serde_json::from_str::<PostMessageResponse>(&text)? {
+            PostMessageResponse { ok: true, ts: Some(ts), .. } => Ok(ts),
+            PostMessageResponse { error, .. } => {
+                Err(AppError::SlackApiFailed(error.unwrap_or(text)))
+            }
+        }
+    }
+
+    /// Checks the message against the Block Kit limits, since Slack rejects
+    /// messages exceeding them.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let block_count = self.blocks.len()
+            + self
+                .attachments
+                .iter()
+                .filter_map(|attachment| attachment["blocks"].as_array())
+                .map(Vec::len)
+                .sum::<usize>();
+        if block_count > MAX_BLOCKS {
+            return Err(ValidationError::TooManyBlocks(block_count));
+        }
+
+        let size = serde_json::to_string(self).map(|json| json.len()).unwrap_or_default();
+        if size > MAX_PAYLOAD_SIZE {
+            return Err(ValidationError::PayloadTooLarge(size));
+        }
+
+        let mut values: Vec<&Value> = self.blocks.iter().chain(&self.attachments).collect();
+        while let Some(value) = values.pop() {
+            match value {
+                Value::Object(object) => {
+                    if let (Some("mrkdwn"), Some(text)) =
+                        (object.get("type").and_then(Value::as_str), object.get("text"))
+                    {
+                        let length = text.as_str().unwrap_or_default().chars().count();
+                        if length > MAX_MRKDWN_TEXT_LENGTH {
+                            return Err(ValidationError::TextTooLong(length));
+                        }
+                    }
+                    values.extend(object.values());
+                }
+                Value::Array(array) => values.extend(array),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn new(message: &Message, config: &Config, annotations: &Annotations) -> Self {
+        let blocks = WebhookMessage::blocks(message, config, annotations);
+        let (blocks, attachments) = match WebhookMessage::color(message, config) {
+            Some(color) => (vec![], vec![json!({ "color": color, "blocks": blocks })]),
+            None => (blocks, vec![]),
+        };
+
+        WebhookMessage {
+            channel: None,
+            thread_ts: None,
+            text: format!(
+                "{} {}",
+                WebhookMessage::emoji(message),
+                config.slack_text_templates.render_plain_text(message, config.notification_locale)
+            ),
+            blocks,
+            attachments,
+            metadata: Some(message.into()),
+        }
+    }
+
+    /// Consolidates a batch of messages into one message, listing each as a
+    /// bullet in a single section.
+    pub fn batch(messages: &[Message]) -> Self {
+        let bullets: Vec<_> = messages
+            .iter()
+            .map(|message| format!("• {} {}", WebhookMessage::emoji(message), message.markdown()))
+            .collect();
+
+        WebhookMessage {
+            channel: None,
+            thread_ts: None,
+            text: format!(":gear: {} GKE notifications", messages.len()),
+            blocks: vec![json!({
+                "type": "section",
+                "text": { "type": "mrkdwn", "text": bullets.join("\n") },
+            })],
+            attachments: vec![],
+            metadata: None,
         }
     }
 
-    fn blocks(message: &Message) -> Vec<Value> {
+    fn blocks(message: &Message, config: &Config, annotations: &Annotations) -> Vec<Value> {
         let attr = &message.attributes;
         let mut result = vec![];
 
+        if let Some(exclusion) = &annotations.maintenance_exclusion {
+            result.push(json!({
+                "type": "section",
+                "text": {
+                    "type": "mrkdwn",
+                    "text": format_args!(
+                        "🚨 *Unexpected upgrade during exclusion* from {} to {}, check the cluster's maintenance exclusions",
+                        exclusion.start.format("%b %-d %H:%M UTC"),
+                        exclusion.end.format("%b %-d %H:%M UTC"),
+                    ),
+                },
+            }));
+        }
+
+        if let Some(p) = attr.payload.as_upgrade_event() {
+            if p.is_rollback() {
+                result.push(json!({
+                    "type": "section",
+                    "text": { "type": "mrkdwn", "text": format_args!("⚠️ *ROLLBACK DETECTED* from `{}` to `{}`", p.current_version, p.target_version) },
+                }));
+            }
+        }
+
         result.push(json!({
             "type": "section",
-            "text": { "type": "mrkdwn", "text": format_args!(":gear: {}", message.markdown()) },
+            "text": { "type": "mrkdwn", "text": format_args!("{} {}", WebhookMessage::emoji(message), message.markdown()) },
         }));
 
         match &attr.payload {
             Payload::SecurityBulletinEvent(p) => {
+                // Slack rejects messages with longer text, descriptions of
+                // bulletins have no limit
+                let description = truncate(format!("*Brief Description*\n{}", p.brief_description));
                 result.push(json!({
                     "type": "section",
-                    "text": { "type": "mrkdwn", "text": format_args!("*Brief Description*\n{}", p.brief_description) },
+                    "text": { "type": "mrkdwn", "text": description },
                 }));
 
                 result.push(json!({
@@ -53,6 +342,15 @@ impl WebhookMessage {
                     ]
                 }));
 
+                if !p.affected_image_types.is_empty() {
+                    result.push(json!({
+                        "type": "section",
+                        "fields": [
+                            { "type": "mrkdwn", "text": format_args!("*Affected Image Types*\n{}", p.affected_image_types.join(", ")) },
+                        ]
+                    }));
+                }
+
                 result.push(json!({
                     "type": "section",
                     "fields": [
@@ -61,6 +359,15 @@ impl WebhookMessage {
                     ]
                 }));
 
+                if let Some(trend) = annotations.severity_trend {
+                    result.push(json!({
+                        "type": "context",
+                        "elements": [
+                            { "type": "mrkdwn", "text": trend.to_string() }
+                        ]
+                    }));
+                }
+
                 if !p.patched_versions.is_empty() || !p.suggested_upgrade_target.is_empty() {
                     result.push(json!({
                         "type": "section",
@@ -71,11 +378,29 @@ impl WebhookMessage {
                     }));
                 }
 
+                if let Some(issued) = message.publish_time() {
+                    let date = |days: i64| (issued + TimeDelta::days(days)).format("%b %-d");
+                    result.push(json!({
+                        "type": "section",
+                        "text": {
+                            "type": "mrkdwn",
+                            "text": format_args!(
+                                "*Timeline*\nBulletin issued ({}) → Manual patching available (~{}) → Auto-upgrade scheduled (~{})",
+                                date(0),
+                                date(MANUAL_PATCHING_DAYS),
+                                date(config.bulletin_auto_upgrade_days.into()),
+                            ),
+                        },
+                    }));
+                }
+
                 result.push(json!({
                     "type": "section",
                     "fields": [
                         { "type": "mrkdwn", "text": format_args!("*Cluster*\n{}", attr.resource_url()) },
                         { "type": "mrkdwn", "text": format_args!("*Security Bulletin*\n<{}|View Details>", p.bulletin_uri) },
+                        { "type": "mrkdwn", "text": format_args!("*GKE Documentation*\n<{}|View Documentation>", p.documentation_url()) },
+                        { "type": "mrkdwn", "text": format_args!("*Cloud Armor*\n<{}|View Security Policies>", attr.cloud_armor_url()) },
                     ]
                 }));
             }
@@ -95,6 +420,125 @@ impl WebhookMessage {
                         { "type": "mrkdwn", "text": format_args!("*Release Channel*\n{}", p.release_channel) },
                     ]
                 }));
+
+                // For comparing the minor versions when deciding whether to upgrade now or wait
+                result.push(json!({
+                    "type": "context",
+                    "elements": [{
+                        "type": "mrkdwn",
+                        "text": format_args!("<{}|View Version History>", p.version_history_url()),
+                    }],
+                }));
+
+                if p.release_channel == ReleaseChannel::Rapid {
+                    result.push(json!({
+                        "type": "context",
+                        "elements": [{
+                            "type": "mrkdwn",
+                            "text": format_args!("This version will be available in REGULAR in approximately {RAPID_TO_REGULAR_WEEKS} weeks."),
+                        }],
+                    }));
+                }
+
+                if let Some(node_pool_size) =
+                    p.node_pool_size().filter(|_| attr.is_node_pool_upgrade_available_event())
+                {
+                    result.push(json!({
+                        "type": "section",
+                        "fields": [
+                            { "type": "mrkdwn", "text": format_args!("*Node Pool:* {node_pool_size}") },
+                        ]
+                    }));
+                }
+
+                if !annotations.changelog.is_empty() {
+                    let bullets: Vec<_> =
+                        annotations.changelog.iter().map(|bullet| format!("• {bullet}")).collect();
+                    result.push(json!({
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": format_args!("*What's new in {}*\n{}", p.version, bullets.join("\n")) },
+                    }));
+                }
+
+                if attr.cluster_auto_upgrade == Some(false) {
+                    result.push(json!({
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": "Auto-upgrade is not configured for this cluster. Consider enabling it." },
+                    }));
+                }
+
+                // The event doesn't include the cluster's current version, until
+                // a control plane version is known the warning is about the
+                // available version, which is the same minor for patch upgrades
+                let (eol_days, subject) = match &annotations.control_plane_version {
+                    Some(current) => (
+                        UpgradeAvailableEvent::current_version_eol_days(current),
+                        format!("the current version {current}"),
+                    ),
+                    None => (
+                        UpgradeAvailableEvent::current_version_eol_days(&p.version),
+                        format!("the available version {}", p.version),
+                    ),
+                };
+                result.extend(WebhookMessage::eol_warning(eol_days, &subject));
+
+                if let Some(version_skew) = &annotations.version_skew {
+                    result.push(json!({
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": version_skew.to_string() },
+                    }));
+                }
+
+                if !annotations.security_patch_for.is_empty() {
+                    let bulletin_ids = annotations.security_patch_for.join("`, `");
+                    result.push(json!({
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": format_args!(":shield: *Security Patch* for `{bulletin_ids}`") },
+                    }));
+                }
+
+                // Surge upgrades create additional nodes, which fails once the quotas are exhausted
+                result.push(json!({
+                    "type": "context",
+                    "elements": [{
+                        "type": "mrkdwn",
+                        "text": format_args!("Upgrades can fail when compute quotas are exhausted. <{}|Check Quotas>", attr.project_quota_url()),
+                    }],
+                }));
+
+                // Shown for control plane upgrades unless SLACK_SHOW_UPGRADE_BUTTON is set
+                let show_upgrade_button = config
+                    .slack_show_upgrade_button
+                    .unwrap_or(attr.is_control_plane_upgrade_available_event());
+
+                let mut buttons = vec![];
+                if show_upgrade_button {
+                    buttons.push(json!({
+                        "type": "button",
+                        "text": { "type": "plain_text", "text": "Initiate Upgrade →" },
+                        "url": attr.upgrade_url(),
+                    }));
+                }
+
+                // Shown when SLACK_SIGNING_SECRET is set, handled by `interaction`
+                if let Some(action_id) = &annotations.approve_upgrade_action_id {
+                    buttons.push(json!({
+                        "type": "button",
+                        "text": { "type": "plain_text", "text": "Approve Upgrade" },
+                        "style": "primary",
+                        "action_id": action_id,
+                        "confirm": {
+                            "title": { "type": "plain_text", "text": "Approve Upgrade" },
+                            "text": { "type": "mrkdwn", "text": format_args!("Upgrade to {} now?", p.version) },
+                            "confirm": { "type": "plain_text", "text": "Upgrade" },
+                            "deny": { "type": "plain_text", "text": "Cancel" },
+                        },
+                    }));
+                }
+
+                if !buttons.is_empty() {
+                    result.push(json!({ "type": "actions", "elements": buttons }));
+                }
             }
             Payload::UpgradeEvent(p) => {
                 result.push(json!({
@@ -112,6 +556,173 @@ impl WebhookMessage {
                         { "type": "mrkdwn", "text": format_args!("*Target Version*\n{}", p.target_version) },
                     ]
                 }));
+
+                let started = match p.elapsed_time() {
+                    Some(_) => format!("{} ago", p.formatted_elapsed()),
+                    None => p.formatted_elapsed(),
+                };
+                let mut fields = vec![json!({
+                    "type": "mrkdwn",
+                    "text": format_args!("*Upgrade Started*\n{started}"),
+                })];
+                if let Some(node_list_url) = attr.node_list_url() {
+                    fields.push(json!({ "type": "mrkdwn", "text": format_args!("*Nodes*\n<{node_list_url}|View Nodes>") }));
+                }
+                match p.estimated_pod_evictions(config.avg_pods_per_node) {
+                    Some(0) | None => {}
+                    Some(1) => fields.push(json!({ "type": "mrkdwn", "text": "*Pod Evictions*\n~1 pod will be evicted" })),
+                    Some(pods) => fields.push(json!({ "type": "mrkdwn", "text": format_args!("*Pod Evictions*\n~{pods} pods will be evicted") })),
+                }
+                result.push(json!({ "type": "section", "fields": fields }));
+
+                if let Some(available_for) = annotations.available_for {
+                    let days = match available_for.num_days() {
+                        0 => "less than a day".to_string(),
+                        1 => "1 day".to_string(),
+                        days => format!("{days} days"),
+                    };
+                    result.push(json!({
+                        "type": "context",
+                        "elements": [{
+                            "type": "mrkdwn",
+                            "text": format_args!("Available for {days} before upgrade started"),
+                        }],
+                    }));
+                }
+
+                // GKE only upgrades within the maintenance windows, if there are any
+                if let Some(policy) = &attr.cluster_maintenance_policy {
+                    let text = match policy.as_str() {
+                        "" | "NONE" => {
+                            "⚠️ *Warning:* No maintenance window configured.".to_string()
+                        }
+                        "RECURRING_WINDOW" => "*Maintenance Policy*\nRecurring window".to_string(),
+                        "DAILY_MAINTENANCE_WINDOW" => {
+                            "*Maintenance Policy*\nDaily window".to_string()
+                        }
+                        policy => format!("*Maintenance Policy*\n{policy}"),
+                    };
+                    result.push(json!({
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": text },
+                    }));
+                }
+
+                result.push(WebhookMessage::recommendations_button(message));
+            }
+            Payload::NodeUpgradeCompleteEvent(p) => {
+                let elapsed = p
+                    .elapsed()
+                    .map(|elapsed| format_duration(elapsed, TimeDelta::seconds(1)))
+                    .unwrap_or_else(|| "Unknown".to_string());
+
+                result.push(json!({
+                    "type": "section",
+                    "fields": [
+                        { "type": "mrkdwn", "text": format_args!("*Project*\n{}", attr.project_name()) },
+                        { "type": "mrkdwn", "text": format_args!("*Target Version*\n{}", p.target_version) },
+                    ]
+                }));
+
+                result.push(json!({
+                    "type": "section",
+                    "fields": [
+                        { "type": "mrkdwn", "text": format_args!("*Resource*\n<{}|View in Console>", attr.resource_url()) },
+                        { "type": "mrkdwn", "text": format_args!("*Elapsed Time*\n{elapsed}") },
+                    ]
+                }));
+
+                result.push(WebhookMessage::recommendations_button(message));
+            }
+            Payload::NodePoolConfigChangeEvent(p) => {
+                let changed_fields: Vec<_> =
+                    p.changed_fields.iter().map(|field| format!("`{field}`")).collect();
+
+                result.push(json!({
+                    "type": "section",
+                    "fields": [
+                        { "type": "mrkdwn", "text": format_args!("*Project*\n{}", attr.project_name()) },
+                        { "type": "mrkdwn", "text": format_args!("*Node Pool*\n<{}|{}>", attr.resource_url(), p.node_pool_name) },
+                    ]
+                }));
+
+                result.push(json!({
+                    "type": "section",
+                    "text": { "type": "mrkdwn", "text": format_args!("*Changed Fields*\n{}", changed_fields.join(", ")) },
+                }));
+            }
+            Payload::NodePoolAutoscalingEvent(p) => {
+                let arrow = if p.node_count_change < 0 { "⬇" } else { "⬆" };
+
+                result.push(json!({
+                    "type": "section",
+                    "fields": [
+                        { "type": "mrkdwn", "text": format_args!("*Project*\n{}", attr.project_name()) },
+                        { "type": "mrkdwn", "text": format_args!("*Node Count*\n{arrow} {:+}", p.node_count_change) },
+                    ]
+                }));
+
+                let node_pool = match p.node_pool_name() {
+                    Some(node_pool_name) => {
+                        format!("*Node Pool*\n<{}|{node_pool_name}>", attr.resource_url())
+                    }
+                    None => format!("*Cluster*\n<{}|View in Console>", attr.resource_url()),
+                };
+                result.push(json!({
+                    "type": "section",
+                    "fields": [
+                        { "type": "mrkdwn", "text": node_pool },
+                        { "type": "mrkdwn", "text": format_args!("*Reason*\n{}", p.reason) },
+                    ]
+                }));
+            }
+            Payload::NodePoolCreatedEvent(p) => {
+                result.push(json!({
+                    "type": "section",
+                    "fields": [
+                        { "type": "mrkdwn", "text": format_args!("*Project*\n{}", attr.project_name()) },
+                        { "type": "mrkdwn", "text": format_args!("*Node Pool*\n<{}|{}>", attr.resource_url(), p.node_pool_name().unwrap_or_default()) },
+                    ]
+                }));
+            }
+            Payload::NodePoolDeletedEvent(p) => {
+                result.push(json!({
+                    "type": "section",
+                    "fields": [
+                        { "type": "mrkdwn", "text": format_args!("*Project*\n{}", attr.project_name()) },
+                        { "type": "mrkdwn", "text": format_args!("*Node Pool*\n{}", p.node_pool_name().unwrap_or_default()) },
+                    ]
+                }));
+
+                result.push(json!({
+                    "type": "section",
+                    "fields": [
+                        { "type": "mrkdwn", "text": format_args!("*Cluster*\n<{}|View in Console>", attr.resource_url()) },
+                    ]
+                }));
+            }
+            Payload::ClusterStatusChangeEvent(p) => {
+                result.push(json!({
+                    "type": "section",
+                    "fields": [
+                        { "type": "mrkdwn", "text": format_args!("*Project*\n{}", attr.project_name()) },
+                        { "type": "mrkdwn", "text": format_args!("*Status*\n{}", p.status) },
+                    ]
+                }));
+
+                if !p.status_message.is_empty() {
+                    result.push(json!({
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": format_args!("*Status Message*\n{}", p.status_message) },
+                    }));
+                }
+
+                result.push(json!({
+                    "type": "section",
+                    "fields": [
+                        { "type": "mrkdwn", "text": format_args!("*Resource*\n<{}|View in Console>", attr.resource_url()) },
+                    ]
+                }));
             }
             _ => {
                 result.push(json!({
@@ -131,30 +742,194 @@ impl WebhookMessage {
             }
         };
 
+        let mut context = vec![json!({ "type": "mrkdwn", "text": attr.resource_uri() })];
+        if let Some(publish_time) = message.publish_time() {
+            context.push(json!({ "type": "mrkdwn", "text": format_received(publish_time) }));
+        }
+        if let Some(days) = attr.payload.as_security_bulletin_event().and_then(|p| p.age_days()) {
+            // Bulletins from a future year would be a clock or ID problem
+            if days >= 0 {
+                let issued = format!("Issued approximately {days} days ago");
+                context.push(json!({ "type": "mrkdwn", "text": issued }));
+            }
+        }
+        if let Some(channel) = &attr.cluster_release_channel {
+            let channel = format!("Cluster Channel: {channel}");
+            context.push(json!({ "type": "mrkdwn", "text": channel }));
+        }
         result.push(json!({
             "type": "context",
-            "elements": [
-                { "type": "mrkdwn", "text": attr.resource_uri() }
-            ]
+            "elements": context
         }));
 
         result
     }
-}
 
-impl From<&Message> for WebhookMessage {
-    fn from(message: &Message) -> Self {
-        WebhookMessage {
-            text: format!(":gear: {}", message.plain_text()),
-            blocks: WebhookMessage::blocks(message),
-        }
+    /// Warns when the end of life of the minor version of `subject` is at
+    /// most `EOL_WARNING_DAYS` away or has passed, or notes that it's unknown
+    /// when the minor version isn't in the end of life table.
+    fn eol_warning(eol_days: Option<i64>, subject: &str) -> Option<Value> {
+        let text = match eol_days {
+            Some(days @ 0..=EOL_WARNING_DAYS) => format!("⚠️ *End of Life* of the minor version of {subject} in {days} days, plan a minor version upgrade"),
+            Some(days @ ..0) => format!("⚠️ *End of Life* of the minor version of {subject} was {} days ago, plan a minor version upgrade", -days),
+            Some(_) => return None,
+            None => {
+                return Some(json!({
+                    "type": "context",
+                    "elements": [{ "type": "mrkdwn", "text": format_args!("End of Life unknown for the minor version of {subject}, see the <https://cloud.google.com/kubernetes-engine/docs/release-schedule|GKE release schedule>") }],
+                }));
+            }
+        };
+
+        Some(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": text },
+        }))
     }
-}
 
-#[cfg(test)]
+    /// Links to the GKE recommendations of the project, which are worth a
+    /// look once a cluster has been upgraded.
+    fn recommendations_button(message: &Message) -> Value {
+        json!({
+            "type": "actions",
+            "elements": [
+                {
+                    "type": "button",
+                    "text": { "type": "plain_text", "text": "View Recommendations" },
+                    "url": message.attributes.cluster_recommendation_url(),
+                },
+            ]
+        })
+    }
+
+    /// The emoji prefixing the message text.
+    /// Upgrades in progress and completed upgrades get different emojis, so
+    /// the progression of an upgrade is visible in the channel.
+    fn emoji(message: &Message) -> &'static str {
+        match &message.attributes.payload {
+            Payload::UpgradeEvent(_) => ":arrow_up:",
+            Payload::NodeUpgradeCompleteEvent(_) => ":white_check_mark:",
+            Payload::NodePoolCreatedEvent(_) => ":large_green_circle:",
+            Payload::NodePoolDeletedEvent(_) => ":red_circle:",
+            _ => ":gear:",
+        }
+    }
+
+    /// The color of the attachment bar the blocks are wrapped in, if any.
+    /// Cluster status changes are always color-coded, other events only
+    /// when `SLACK_USE_ATTACHMENTS` is set.
+    fn color(message: &Message, config: &Config) -> Option<&'static str> {
+        match &message.attributes.payload {
+            Payload::ClusterStatusChangeEvent(p) => match p.status {
+                ClusterStatus::Error => Some("#FF0000"),
+                ClusterStatus::Running => Some("#36A64F"),
+                _ => None,
+            },
+            _ if !config.slack_use_attachments => None,
+            Payload::SecurityBulletinEvent(_) => Some("#FF0000"),
+            Payload::UpgradeEvent(_) => Some("#FFA500"),
+            Payload::UpgradeAvailableEvent(_) => Some("#36A64F"),
+            _ => None,
+        }
+    }
+}
+
+/// Truncates mrkdwn text to `MAX_MRKDWN_TEXT_LENGTH` characters, ending
+/// truncated text with `…`.
+fn truncate(text: String) -> String {
+    match text.char_indices().nth(MAX_MRKDWN_TEXT_LENGTH - 1) {
+        Some((end, _)) if text.chars().count() > MAX_MRKDWN_TEXT_LENGTH => {
+            format!("{}…", &text[..end])
+        }
+        _ => text,
+    }
+}
+
+/// Formats how long ago a message was published, e.g. `Received 3 minutes
+/// ago`, which shows how stale it is when delivery to Slack was delayed. The
+/// age is rendered by Slack when the message is viewed, so it stays correct
+/// for batched and delayed posts; clients which can't render dates show the
+/// publish time instead.
+fn format_received(publish_time: DateTime<Utc>) -> String {
+    format!(
+        "<!date^{}^Received {{ago}}|Received at {}>",
+        publish_time.timestamp(),
+        publish_time.to_rfc3339_opts(SecondsFormat::Secs, true)
+    )
+}
+
+/// Details about a message derived from previously processed messages,
+/// which are shown alongside it.
+#[derive(Debug, Default)]
+pub struct Annotations {
+    /// The IDs of known security bulletins patched by the version of an
+    /// `UpgradeAvailableEvent`, see `KnownBulletins`.
+    pub security_patch_for: Vec<String>,
+
+    /// How the severity of a `SecurityBulletinEvent` compares to recent
+    /// bulletins, see `SeverityHistory`.
+    pub severity_trend: Option<SeverityTrend>,
+
+    /// The `action_id` of the "Approve Upgrade" button of an
+    /// `UpgradeAvailableEvent`, see `PendingUpgrades`.
+    pub approve_upgrade_action_id: Option<String>,
+
+    /// The skew between the version of a node pool `UpgradeAvailableEvent`
+    /// and its control plane, see `ControlPlaneVersions`.
+    pub version_skew: Option<VersionSkew>,
+
+    /// The running control plane version of the cluster of an
+    /// `UpgradeAvailableEvent`, see `ControlPlaneVersions`.
+    pub control_plane_version: Option<String>,
+
+    /// The first bullet points of the changelog entry for the version of an
+    /// `UpgradeAvailableEvent`, see `Changelog`.
+    pub changelog: Vec<String>,
+
+    /// How long the target version of an `UpgradeEvent` had been available
+    /// when the upgrade started, see `AvailableVersions`.
+    pub available_for: Option<TimeDelta>,
+
+    /// The maintenance exclusion an `UpgradeEvent` happened during, which
+    /// GKE shouldn't do.
+    pub maintenance_exclusion: Option<MaintenanceExclusion>,
+}
+
+/// Maps channels and cluster names to the `ts` of the first message posted
+/// about the cluster, so that notifications for the same cluster (e.g. the
+/// stages of an upgrade) are grouped into a thread. Entries expire after
+/// the TTL.
+#[derive(Clone, Debug)]
+pub struct SlackThreadCache {
+    threads: Arc<Mutex<HashMap<String, (String, Instant)>>>,
+    ttl: Duration,
+}
+
+impl SlackThreadCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { threads: Arc::new(Mutex::new(HashMap::new())), ttl }
+    }
+
+    pub fn get(&self, channel: &str, cluster_name: &str) -> Option<String> {
+        let mut threads = self.threads.lock().unwrap();
+        threads.retain(|_, (_, posted)| posted.elapsed() < self.ttl);
+        threads.get(&format!("{channel}/{cluster_name}")).map(|(thread_ts, _)| thread_ts.clone())
+    }
+
+    pub fn insert(&self, channel: &str, cluster_name: &str, thread_ts: String) {
+        let key = format!("{channel}/{cluster_name}");
+        self.threads.lock().unwrap().insert(key, (thread_ts, Instant::now()));
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::message::attributes::payload::{eol_days, ReleaseChannel, ResourceType};
     use crate::message::tests::test_messages;
+    use chrono::NaiveDate;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
     async fn post() {
@@ -162,14 +937,1002 @@ mod tests {
             if test.message.is_invalid() {
                 continue;
             }
-            let message: WebhookMessage = (&test.message).into();
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
 
             if let Ok(webhook) = std::env::var("SLACK_WEBHOOK") {
-                message.post(webhook).await.unwrap();
+                message
+                    .post_to_webhook(&reqwest::Client::new(), &NotifierConfig::new(webhook))
+                    .await
+                    .unwrap();
             }
 
             // Print JSON usable in Block Kit Builder preview: https://app.slack.com/block-kit-builder/
-            println!("{}\n", json!({ "blocks": message.blocks }));
+            println!(
+                "{}\n",
+                json!({ "blocks": message.blocks, "attachments": message.attachments })
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn post_errors() {
+        let test = test_messages().into_iter().find(|test| !test.message.is_invalid()).unwrap();
+        let mut message =
+            WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+
+        let slack = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("no_service"))
+            .mount(&slack)
+            .await;
+
+        match message
+            .post_to_webhook(&reqwest::Client::new(), &NotifierConfig::new(slack.uri()))
+            .await
+        {
+            Err(AppError::HttpPostFailed { status: 404, body }) => assert_eq!(body, "no_service"),
+            res => panic!("expected AppError::HttpPostFailed, got {res:?}"),
+        }
+
+        // Unconfirmed deliveries are only logged
+        let slack = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("invalid_payload"))
+            .mount(&slack)
+            .await;
+        assert_eq!(
+            message
+                .post_to_webhook(&reqwest::Client::new(), &NotifierConfig::new(slack.uri()))
+                .await
+                .unwrap(),
+            "invalid_payload"
+        );
+
+        let slack = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "ok": false, "error": "channel_not_found" })),
+            )
+            .mount(&slack)
+            .await;
+
+        match message
+            .post_message(&reqwest::Client::new(), &slack.uri(), "xoxb-test", "#gke", None)
+            .await
+        {
+            Err(AppError::SlackApiFailed(err)) => assert_eq!(err, "channel_not_found"),
+            res => panic!("expected AppError::SlackApiFailed, got {res:?}"),
+        }
+
+        match message
+            .post_to_webhook(
+                &reqwest::Client::new(),
+                &NotifierConfig::new("http://127.0.0.1:0".to_string()),
+            )
+            .await
+        {
+            Err(AppError::RequestFailed(_)) => {}
+            res => panic!("expected AppError::RequestFailed, got {res:?}"),
+        }
+    }
+
+    #[test]
+    fn use_attachments() {
+        let config = Config { slack_use_attachments: true, ..Default::default() };
+        for test in test_messages() {
+            let message = WebhookMessage::new(&test.message, &config, &Annotations::default());
+            let expected = match &test.message.attributes.payload {
+                Payload::SecurityBulletinEvent(_) => Some("#FF0000"),
+                Payload::UpgradeEvent(_) => Some("#FFA500"),
+                Payload::UpgradeAvailableEvent(_) => Some("#36A64F"),
+                Payload::ClusterStatusChangeEvent(p) => match p.status {
+                    ClusterStatus::Error => Some("#FF0000"),
+                    ClusterStatus::Running => Some("#36A64F"),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            assert_eq!(
+                message.attachments.first().and_then(|attachment| attachment["color"].as_str()),
+                expected,
+                "{}",
+                test.name
+            );
+            assert_eq!(message.blocks.is_empty(), expected.is_some(), "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn attachment_color() {
+        for test in test_messages() {
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let expected = match &test.message.attributes.payload {
+                Payload::ClusterStatusChangeEvent(p) if p.status == ClusterStatus::Error => {
+                    Some("#FF0000")
+                }
+                Payload::ClusterStatusChangeEvent(p) if p.status == ClusterStatus::Running => {
+                    Some("#36A64F")
+                }
+                _ => None,
+            };
+
+            match expected {
+                Some(color) => {
+                    assert!(message.blocks.is_empty());
+                    assert_eq!(message.attachments.len(), 1);
+                    assert_eq!(message.attachments[0]["color"], color);
+                }
+                None => {
+                    assert!(!message.blocks.is_empty());
+                    assert!(message.attachments.is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn upgrade_button() {
+        for test in test_messages() {
+            let attr = &test.message.attributes;
+            let Some(p) = attr.payload.as_upgrade_available_event() else {
+                continue;
+            };
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let button = message
+                .blocks
+                .iter()
+                .find(|block| block["type"] == "actions")
+                .map(|block| block["elements"][0].clone());
+
+            match p.resource_type {
+                ResourceType::ControlPlane => {
+                    let button = button.expect("control plane should have an upgrade button");
+                    assert_eq!(button["text"]["text"], "Initiate Upgrade →");
+                    assert_eq!(
+                        button["url"],
+                        format!(
+                            "https://console.cloud.google.com/kubernetes/clusters/details/us-central1/test-cluster/upgrade?project={}",
+                            attr.project_name()
+                        )
+                    );
+                }
+                _ => assert!(button.is_none()),
+            }
+        }
+    }
+
+    #[test]
+    fn thread_cache() {
+        let cache = SlackThreadCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get("C01", "test-cluster"), None);
+
+        cache.insert("C01", "test-cluster", "1673639484.884".to_string());
+        assert_eq!(cache.get("C01", "test-cluster"), Some("1673639484.884".to_string()));
+        assert_eq!(cache.get("C01", "other-cluster"), None);
+        assert_eq!(cache.get("C02", "test-cluster"), None);
+
+        let cache = SlackThreadCache::new(Duration::ZERO);
+        cache.insert("C01", "test-cluster", "1673639484.884".to_string());
+        assert_eq!(cache.get("C01", "test-cluster"), None);
+    }
+
+    #[test]
+    fn affected_image_types() {
+        for test in test_messages() {
+            let Some(p) = test.message.attributes.payload.as_security_bulletin_event() else {
+                continue;
+            };
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let field = message
+                .blocks
+                .iter()
+                .filter_map(|block| block["fields"].as_array())
+                .flatten()
+                .find(|field| {
+                    field["text"].as_str().unwrap().starts_with("*Affected Image Types*")
+                });
+
+            match p.affected_image_types.is_empty() {
+                true => assert!(field.is_none()),
+                false => assert_eq!(field.unwrap()["text"], "*Affected Image Types*\nCOS, Ubuntu"),
+            }
+        }
+    }
+
+    #[test]
+    fn security_patch() {
+        for test in test_messages() {
+            if test.message.attributes.payload.as_upgrade_available_event().is_none() {
+                continue;
+            }
+
+            let annotations = Annotations {
+                security_patch_for: vec!["GCP-2022-002".into(), "GCP-2022-005".into()],
+                ..Default::default()
+            };
+            let message = WebhookMessage::new(&test.message, &Config::default(), &annotations);
+            assert!(message.blocks.iter().any(|block| block["text"]["text"]
+                == ":shield: *Security Patch* for `GCP-2022-002`, `GCP-2022-005`"));
+
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            assert!(!message.blocks.iter().any(|block| block["text"]["text"]
+                .as_str()
+                .is_some_and(|text| text.starts_with(":shield:"))));
+        }
+    }
+
+    #[test]
+    fn node_upgrade_complete() {
+        for test in test_messages() {
+            let Payload::NodeUpgradeCompleteEvent(_) = &test.message.attributes.payload else {
+                continue;
+            };
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+
+            assert!(message.text.starts_with(":white_check_mark: "));
+            assert!(message
+                .blocks
+                .iter()
+                .filter_map(|block| block["fields"].as_array())
+                .flatten()
+                .any(|field| field["text"] == "*Elapsed Time*\n36m 47s"));
+        }
+    }
+
+    #[test]
+    fn node_pool_config_change() {
+        let test = test_messages()
+            .into_iter()
+            .find(|test| test.name == "NodePoolConfigChangeEvent")
+            .unwrap();
+        let message =
+            WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+
+        assert_eq!(
+            message.blocks[1]["fields"][1]["text"],
+            "*Node Pool*\n<https://console.cloud.google.com/kubernetes/nodepool/us-central1/test-cluster/default-pool?project=0123456789|default-pool>"
+        );
+        assert_eq!(
+            message.blocks[2]["text"]["text"],
+            "*Changed Fields*\n`machineType`, `diskSizeGb`"
+        );
+    }
+
+    #[test]
+    fn node_pool_autoscaling() {
+        for test in test_messages() {
+            let Payload::NodePoolAutoscalingEvent(p) = &test.message.attributes.payload else {
+                continue;
+            };
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+
+            let expected = match p.node_count_change {
+                3 => "*Node Count*\n⬆ +3",
+                -2 => "*Node Count*\n⬇ -2",
+                _ => unreachable!("{}", test.name),
+            };
+            assert_eq!(message.blocks[1]["fields"][1]["text"], expected, "{}", test.name);
+            assert_eq!(
+                message.blocks[2]["fields"][0]["text"],
+                "*Node Pool*\n<https://console.cloud.google.com/kubernetes/nodepool/us-central1/test-cluster/default-pool?project=0123456789|default-pool>",
+                "{}",
+                test.name
+            );
+        }
+
+        let mut message = test_messages()
+            .into_iter()
+            .find(|test| {
+                matches!(test.message.attributes.payload, Payload::NodePoolAutoscalingEvent(_))
+            })
+            .unwrap()
+            .message;
+        let Payload::NodePoolAutoscalingEvent(p) = &mut message.attributes.payload else {
+            unreachable!()
+        };
+        p.resource = None;
+        let webhook_message =
+            WebhookMessage::new(&message, &Config::default(), &Annotations::default());
+        assert_eq!(message.plain_text(), "test-cluster was autoscaled by +3 nodes");
+        assert_eq!(message.markdown(), "*`test-cluster`* was autoscaled by +3 nodes");
+        assert_eq!(
+            webhook_message.blocks[2]["fields"][0]["text"],
+            "*Cluster*\n<https://console.cloud.google.com/kubernetes/clusters/details/us-central1/test-cluster?project=0123456789|View in Console>"
+        );
+    }
+
+    #[test]
+    fn emoji() {
+        for test in test_messages() {
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let expected = match &test.message.attributes.payload {
+                Payload::UpgradeEvent(_) => ":arrow_up:",
+                Payload::NodeUpgradeCompleteEvent(_) => ":white_check_mark:",
+                Payload::NodePoolCreatedEvent(_) => ":large_green_circle:",
+                Payload::NodePoolDeletedEvent(_) => ":red_circle:",
+                _ => ":gear:",
+            };
+            assert!(message.text.starts_with(expected), "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn brief_description_truncated() {
+        let mut test = test_messages()
+            .into_iter()
+            .find(|test| test.message.attributes.payload.as_security_bulletin_event().is_some())
+            .unwrap();
+        let Some(p) = test.message.attributes.payload.as_security_bulletin_event_mut() else {
+            unreachable!()
+        };
+        p.brief_description = "a".repeat(3001);
+
+        let message =
+            WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+        let text = message
+            .blocks
+            .iter()
+            .find_map(|block| block["text"]["text"].as_str()?.strip_prefix("*Brief Description*\n"))
+            .unwrap();
+
+        assert_eq!(format!("*Brief Description*\n{text}").chars().count(), 3000);
+        assert!(text.ends_with("a…"), "{text}");
+        assert_eq!(message.validate(), Ok(()));
+
+        assert_eq!(truncate("short".to_string()), "short");
+        assert_eq!(truncate("é".repeat(3000)), "é".repeat(3000));
+        assert_eq!(truncate("é".repeat(3001)), format!("{}…", "é".repeat(2999)));
+    }
+
+    #[test]
+    fn format_received() {
+        let publish_time = DateTime::parse_from_rfc3339("2022-03-03T18:00:00Z").unwrap().to_utc();
+        assert_eq!(
+            super::format_received(publish_time),
+            "<!date^1646330400^Received {ago}|Received at 2022-03-03T18:00:00Z>"
+        );
+
+        let test = test_messages().into_iter().find(|test| !test.message.is_invalid()).unwrap();
+        let message =
+            WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+        let context = &message.blocks.last().unwrap()["elements"];
+        assert!(context[1]["text"].as_str().unwrap().starts_with("<!date^"));
+    }
+
+    #[test]
+    fn cluster_release_channel() {
+        let test = test_messages().into_iter().find(|test| !test.message.is_invalid()).unwrap();
+        let mut message = test.message;
+        let channel_context = |message: &Message| {
+            let slack_message =
+                WebhookMessage::new(message, &Config::default(), &Annotations::default());
+            slack_message.blocks.last().unwrap()["elements"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find_map(|element| element["text"].as_str()?.strip_prefix("Cluster Channel: "))
+                .map(String::from)
+        };
+
+        assert_eq!(channel_context(&message), None);
+        message.attributes.cluster_release_channel = Some(ReleaseChannel::Stable);
+        assert_eq!(channel_context(&message).as_deref(), Some("STABLE"));
+    }
+
+    #[test]
+    fn maintenance_policy() {
+        let mut message = test_messages()
+            .into_iter()
+            .find(|test| test.message.attributes.payload.as_upgrade_event().is_some())
+            .unwrap()
+            .message;
+        let mut policy_text = |policy: Option<&str>| {
+            message.attributes.cluster_maintenance_policy = policy.map(String::from);
+            let message =
+                WebhookMessage::new(&message, &Config::default(), &Annotations::default());
+            message.blocks.iter().find_map(|block| {
+                let text = block["text"]["text"].as_str()?;
+                (text.starts_with("*Maintenance Policy*") || text.contains("No maintenance window"))
+                    .then(|| text.to_string())
+            })
+        };
+
+        assert_eq!(policy_text(None), None);
+        assert_eq!(
+            policy_text(Some("RECURRING_WINDOW")).as_deref(),
+            Some("*Maintenance Policy*\nRecurring window")
+        );
+        assert_eq!(
+            policy_text(Some("NONE")).as_deref(),
+            Some("⚠️ *Warning:* No maintenance window configured.")
+        );
+        assert_eq!(
+            policy_text(Some("")).as_deref(),
+            Some("⚠️ *Warning:* No maintenance window configured.")
+        );
+    }
+
+    #[test]
+    fn bulletin_timeline() {
+        let config = Config { bulletin_auto_upgrade_days: 30, ..Config::default() };
+        for test in test_messages() {
+            let message = WebhookMessage::new(&test.message, &config, &Annotations::default());
+            let timeline = message
+                .blocks
+                .iter()
+                .find_map(|block| block["text"]["text"].as_str()?.strip_prefix("*Timeline*\n"));
+
+            match test.message.attributes.payload.as_security_bulletin_event() {
+                Some(_) => assert_eq!(
+                    timeline,
+                    Some("Bulletin issued (Jan 13) → Manual patching available (~Jan 20) → Auto-upgrade scheduled (~Feb 12)"),
+                    "{}",
+                    test.name
+                ),
+                None => assert_eq!(timeline, None, "{}", test.name),
+            }
+        }
+    }
+
+    #[test]
+    fn bulletin_age() {
+        for test in test_messages() {
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let json = serde_json::to_string(&message).unwrap();
+            let issued = json
+                .split_once("Issued approximately ")
+                .and_then(|(_, rest)| rest.split_once(" days ago"))
+                .map(|(days, _)| days.parse::<i64>().unwrap());
+
+            match test.message.attributes.payload.as_security_bulletin_event() {
+                Some(p) if p.bulletin_id.to_uppercase().starts_with("GCP-2022-") => {
+                    assert!(issued.unwrap() >= 1384, "{}: {issued:?}", test.name);
+                }
+                _ => assert_eq!(issued, None, "{}", test.name),
+            }
+        }
+    }
+
+    #[test]
+    fn node_pool_created_deleted() {
+        for test in test_messages() {
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            match &test.message.attributes.payload {
+                Payload::NodePoolCreatedEvent(_) => assert_eq!(
+                    message.blocks[1]["fields"][1]["text"],
+                    "*Node Pool*\n<https://console.cloud.google.com/kubernetes/nodepool/us-central1/test-cluster/pool-2?project=0123456789|pool-2>"
+                ),
+                Payload::NodePoolDeletedEvent(_) => {
+                    assert_eq!(message.blocks[1]["fields"][1]["text"], "*Node Pool*\npool-2");
+                    assert_eq!(
+                        message.blocks[2]["fields"][0]["text"],
+                        "*Cluster*\n<https://console.cloud.google.com/kubernetes/clusters/details/us-central1/test-cluster?project=0123456789|View in Console>"
+                    );
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    #[test]
+    fn documentation_link() {
+        for test in test_messages() {
+            let Some(p) = test.message.attributes.payload.as_security_bulletin_event() else {
+                continue;
+            };
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let field = message
+                .blocks
+                .iter()
+                .filter_map(|block| block["fields"].as_array())
+                .flatten()
+                .find_map(|field| field["text"].as_str()?.strip_prefix("*GKE Documentation*\n"))
+                .unwrap_or_else(|| panic!("{} should link the documentation", test.name));
+
+            assert_eq!(
+                field,
+                format!("<{}|View Documentation>", p.documentation_url()),
+                "{}",
+                test.name
+            );
+        }
+    }
+
+    #[test]
+    fn severity_trend() {
+        for test in test_messages() {
+            let Some(_) = test.message.attributes.payload.as_security_bulletin_event() else {
+                continue;
+            };
+            let annotations = Annotations {
+                severity_trend: Some(SeverityTrend::Increasing),
+                ..Default::default()
+            };
+            let message = WebhookMessage::new(&test.message, &Config::default(), &annotations);
+
+            assert!(message
+                .blocks
+                .iter()
+                .any(|block| block["elements"][0]["text"] == "↑ severity increasing"));
+        }
+    }
+
+    #[test]
+    fn version_skew() {
+        for test in test_messages() {
+            if test.message.attributes.payload.as_upgrade_available_event().is_none() {
+                continue;
+            }
+            let version_skew = VersionSkew {
+                node_pool_version: "1.27.3-gke.100".to_string(),
+                control_plane_version: "1.29.4-gke.300".to_string(),
+                minor_versions: 2,
+            };
+            let annotations =
+                Annotations { version_skew: Some(version_skew.clone()), ..Default::default() };
+            let message = WebhookMessage::new(&test.message, &Config::default(), &annotations);
+
+            let text = version_skew.to_string();
+            assert!(text.starts_with("⚠️ *Version Skew* of 2 minor versions"), "{text}");
+            assert!(
+                message.blocks.iter().any(|block| block["text"]["text"] == text.as_str()),
+                "{}",
+                test.name
+            );
+        }
+    }
+
+    #[test]
+    fn available_for() {
+        let message = test_messages()
+            .into_iter()
+            .find(|test| test.message.attributes.payload.as_upgrade_event().is_some())
+            .unwrap()
+            .message;
+        let available_text = |available_for: Option<TimeDelta>| {
+            let annotations = Annotations { available_for, ..Default::default() };
+            let message = WebhookMessage::new(&message, &Config::default(), &annotations);
+            message.blocks.iter().find_map(|block| {
+                let text = block["elements"][0]["text"].as_str()?;
+                text.starts_with("Available for").then(|| text.to_string())
+            })
+        };
+
+        assert_eq!(available_text(None), None);
+        assert_eq!(
+            available_text(Some(TimeDelta::hours(30))).as_deref(),
+            Some("Available for 1 day before upgrade started")
+        );
+        assert_eq!(
+            available_text(Some(TimeDelta::days(12))).as_deref(),
+            Some("Available for 12 days before upgrade started")
+        );
+        assert_eq!(
+            available_text(Some(TimeDelta::hours(3))).as_deref(),
+            Some("Available for less than a day before upgrade started")
+        );
+    }
+
+    #[test]
+    fn changelog_preview() {
+        for test in test_messages() {
+            let Some(p) = test.message.attributes.payload.as_upgrade_available_event() else {
+                continue;
+            };
+            let annotations = Annotations {
+                changelog: vec![
+                    "Fixed a kubelet memory leak.".to_string(),
+                    "Updated containerd.".to_string(),
+                ],
+                ..Default::default()
+            };
+            let preview = |annotations: &Annotations| {
+                WebhookMessage::new(&test.message, &Config::default(), annotations)
+                    .blocks
+                    .iter()
+                    .find_map(|block| {
+                        block["text"]["text"].as_str()?.strip_prefix("*What's new in ")
+                    })
+                    .map(String::from)
+            };
+
+            assert_eq!(
+                preview(&annotations),
+                Some(format!(
+                    "{}*\n• Fixed a kubelet memory leak.\n• Updated containerd.",
+                    p.version
+                )),
+                "{}",
+                test.name
+            );
+            assert_eq!(preview(&Annotations::default()), None, "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn rapid_to_regular() {
+        for mut test in test_messages() {
+            let Some(p) = test.message.attributes.payload.as_upgrade_available_event_mut() else {
+                continue;
+            };
+            let note = |message: &Message| {
+                WebhookMessage::new(message, &Config::default(), &Annotations::default())
+                    .blocks
+                    .iter()
+                    .any(|block| {
+                        block["elements"][0]["text"]
+                            == "This version will be available in REGULAR in approximately 2 weeks."
+                    })
+            };
+
+            p.release_channel = ReleaseChannel::Rapid;
+            assert!(note(&test.message), "{}", test.name);
+
+            let Some(p) = test.message.attributes.payload.as_upgrade_available_event_mut() else {
+                unreachable!();
+            };
+            p.release_channel = ReleaseChannel::Regular;
+            assert!(!note(&test.message), "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn node_pool_size() {
+        for mut test in test_messages() {
+            let Some(p) = test.message.attributes.payload.as_upgrade_available_event_mut() else {
+                continue;
+            };
+            p.node_count = Some(3);
+            p.preemptible_count = Some(1);
+
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let field = message
+                .blocks
+                .iter()
+                .filter_map(|block| block["fields"].as_array())
+                .flatten()
+                .find(|field| field["text"].as_str().unwrap().starts_with("*Node Pool:*"));
+
+            if test.message.attributes.is_node_pool_upgrade_available_event() {
+                let field = field.unwrap_or_else(|| panic!("{} should show the size", test.name));
+                assert_eq!(field["text"], "*Node Pool:* 3 nodes (1 preemptible)");
+            } else {
+                assert!(field.is_none(), "{}", test.name);
+            }
+        }
+    }
+
+    #[test]
+    fn upgrade_started() {
+        for test in test_messages() {
+            let Some(p) = test.message.attributes.payload.as_upgrade_event() else {
+                continue;
+            };
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let field = message
+                .blocks
+                .iter()
+                .filter_map(|block| block["fields"].as_array())
+                .flatten()
+                .find_map(|field| field["text"].as_str()?.strip_prefix("*Upgrade Started*\n"))
+                .unwrap_or_else(|| panic!("{} should show when the upgrade started", test.name));
+
+            match p.elapsed_time() {
+                Some(_) => assert!(field.ends_with(" ago"), "{}: {field}", test.name),
+                None => assert_eq!(field, "Unknown", "{}", test.name),
+            }
+        }
+    }
+
+    #[test]
+    fn pod_evictions() {
+        let config = Config { avg_pods_per_node: 10, ..Default::default() };
+        for test in test_messages() {
+            if test.message.attributes.payload.as_upgrade_event().is_none() {
+                continue;
+            }
+
+            let mut message = test.message;
+            let evictions = |message: &Message| {
+                WebhookMessage::new(message, &config, &Annotations::default())
+                    .blocks
+                    .iter()
+                    .filter_map(|block| block["fields"].as_array())
+                    .flatten()
+                    .find_map(|field| field["text"].as_str()?.strip_prefix("*Pod Evictions*\n"))
+                    .map(String::from)
+            };
+            assert_eq!(evictions(&message), None, "{}", test.name);
+
+            let Some(p) = message.attributes.payload.as_upgrade_event_mut() else {
+                unreachable!();
+            };
+            p.node_count = Some(3);
+            let expected = match p.resource_type {
+                ResourceType::NodePool => Some("~30 pods will be evicted".to_string()),
+                _ => None,
+            };
+            assert_eq!(evictions(&message), expected, "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn node_list_link() {
+        for test in test_messages() {
+            let Some(p) = test.message.attributes.payload.as_upgrade_event() else {
+                continue;
+            };
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let field = message
+                .blocks
+                .iter()
+                .filter_map(|block| block["fields"].as_array())
+                .flatten()
+                .find(|field| field["text"].as_str().unwrap().starts_with("*Nodes*"));
+
+            match p.resource_type {
+                ResourceType::NodePool => assert_eq!(
+                    field.unwrap()["text"],
+                    "*Nodes*\n<https://console.cloud.google.com/kubernetes/nodepool/us-central1/test-cluster/nap-e2-medium-ww57dx1i/nodes?project=0123456789|View Nodes>"
+                ),
+                _ => assert!(field.is_none()),
+            }
+        }
+    }
+
+    #[test]
+    fn cloud_armor_link() {
+        for test in test_messages() {
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let cloud_armor_url = test.message.attributes.cloud_armor_url();
+            let link = serde_json::to_string(&message)
+                .unwrap()
+                .contains(&format!("<{cloud_armor_url}|View Security Policies>"));
+
+            let expected = test.message.attributes.payload.as_security_bulletin_event().is_some();
+            assert_eq!(link, expected, "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn version_history_link() {
+        for test in test_messages() {
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let link = message.blocks.iter().find_map(|block| {
+                let text = block["elements"][0]["text"].as_str()?;
+                text.contains("|View Version History>").then_some(text)
+            });
+
+            match test.message.attributes.payload.as_upgrade_available_event() {
+                Some(p) => {
+                    let expected = format!("<{}|View Version History>", p.version_history_url());
+                    assert_eq!(link, Some(expected.as_str()), "{}", test.name);
+                }
+                None => assert_eq!(link, None, "{}", test.name),
+            }
+        }
+    }
+
+    #[test]
+    fn quota_link() {
+        for test in test_messages() {
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let quota_url = test.message.attributes.project_quota_url();
+            let link = serde_json::to_string(&message)
+                .unwrap()
+                .contains(&format!("<{quota_url}|Check Quotas>"));
+
+            let expected = test.message.attributes.payload.as_upgrade_available_event().is_some();
+            assert_eq!(link, expected, "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn approve_upgrade_button() {
+        for test in test_messages() {
+            if test.message.attributes.payload.as_upgrade_available_event().is_none() {
+                continue;
+            }
+            let annotations = Annotations {
+                approve_upgrade_action_id: Some("approve_upgrade:test".to_string()),
+                ..Default::default()
+            };
+            let message = WebhookMessage::new(&test.message, &Config::default(), &annotations);
+            let actions = message.blocks.iter().find(|block| block["type"] == "actions").unwrap();
+            let button = actions["elements"].as_array().unwrap().last().unwrap();
+
+            assert_eq!(button["text"]["text"], "Approve Upgrade", "{}", test.name);
+            assert_eq!(button["action_id"], "approve_upgrade:test", "{}", test.name);
+            assert!(button["confirm"].is_object(), "{}", test.name);
+        }
+    }
+
+    #[test]
+    fn recommendations_button() {
+        for test in test_messages() {
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let button = message
+                .blocks
+                .iter()
+                .filter(|block| block["type"] == "actions")
+                .flat_map(|block| block["elements"].as_array().unwrap())
+                .find(|element| element["text"]["text"] == "View Recommendations");
+
+            match &test.message.attributes.payload {
+                Payload::UpgradeEvent(_) | Payload::NodeUpgradeCompleteEvent(_) => assert_eq!(
+                    button.unwrap()["url"],
+                    "https://console.cloud.google.com/home/recommendations?project=0123456789"
+                ),
+                _ => assert!(button.is_none()),
+            }
+        }
+    }
+
+    #[test]
+    fn eol_warning() {
+        let today = NaiveDate::from_ymd_opt(2023, 2, 28).unwrap();
+        let warning = |version| {
+            let block = WebhookMessage::eol_warning(eol_days(version, today), version)?;
+            let text = block["text"]["text"].as_str().or(block["elements"][0]["text"].as_str());
+            text.map(String::from)
+        };
+
+        assert_eq!(
+            warning("1.22.6-gke.300").as_deref(),
+            Some("⚠️ *End of Life* of the minor version of 1.22.6-gke.300 in 61 days, plan a minor version upgrade")
+        );
+        assert_eq!(
+            warning("1.21.9-gke.200").as_deref(),
+            Some("⚠️ *End of Life* of the minor version of 1.21.9-gke.200 was 28 days ago, plan a minor version upgrade")
+        );
+        assert_eq!(warning("1.24.1-gke.100"), None, "more than 90 days away");
+        assert!(warning("1.99.1-gke.100").unwrap().starts_with("End of Life unknown"));
+
+        let eol_text = |message: &WebhookMessage| {
+            message.blocks.iter().find_map(|block| {
+                let text = block["text"]["text"].as_str().or(block["elements"][0]["text"].as_str());
+                text.filter(|text| text.contains("End of Life")).map(String::from)
+            })
+        };
+        for test in test_messages() {
+            let Some(p) = test.message.attributes.payload.as_upgrade_available_event() else {
+                continue;
+            };
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let text = eol_text(&message).unwrap_or_else(|| panic!("{}", test.name));
+            assert!(text.contains(&format!("the available version {}", p.version)), "{text}");
+
+            // 1.21 reached its end of life long ago, whatever the available version
+            let annotations = Annotations {
+                control_plane_version: Some("1.21.9-gke.200".to_string()),
+                ..Default::default()
+            };
+            let message = WebhookMessage::new(&test.message, &Config::default(), &annotations);
+            let text = eol_text(&message).unwrap_or_else(|| panic!("{}", test.name));
+            assert!(text.contains("the current version 1.21.9-gke.200 was"), "{text}");
+        }
+    }
+
+    #[test]
+    fn batch() {
+        let messages: Vec<_> = test_messages()
+            .into_iter()
+            .filter(|test| test.name.starts_with("UpgradeEvent:"))
+            .map(|test| test.message)
+            .collect();
+        let message = WebhookMessage::batch(&messages);
+
+        assert_eq!(message.text, format!(":gear: {} GKE notifications", messages.len()));
+        assert_eq!(message.blocks.len(), 1);
+        assert_eq!(
+            message.blocks[0]["text"]["text"].as_str().unwrap().lines().collect::<Vec<_>>(),
+            messages
+                .iter()
+                .map(|message| format!("• :arrow_up: {}", message.markdown()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let bulletin = |brief_description: String| {
+            let mut message = test_messages()
+                .into_iter()
+                .find(|test| test.name.starts_with("SecurityBulletinEvent"))
+                .unwrap()
+                .message;
+            let Some(p) = message.attributes.payload.as_security_bulletin_event_mut() else {
+                unreachable!()
+            };
+            p.brief_description = brief_description;
+            p.cve_ids = (0..1000).map(|i| format!("CVE-2024-{i:05}")).collect();
+            WebhookMessage::new(&message, &Config::default(), &Annotations::default())
+        };
+
+        // "*Brief Description*\n" is 20 characters, longer descriptions are truncated
+        assert_eq!(bulletin("x".repeat(MAX_MRKDWN_TEXT_LENGTH - 20)).validate(), Ok(()));
+        assert_eq!(bulletin("x".repeat(MAX_MRKDWN_TEXT_LENGTH - 19)).validate(), Ok(()));
+
+        let messages: Vec<_> = (0..10)
+            .flat_map(|_| test_messages())
+            .filter(|test| !test.message.is_invalid())
+            .map(|test| test.message)
+            .collect();
+        assert!(matches!(
+            WebhookMessage::batch(&messages).validate(),
+            Err(ValidationError::TextTooLong(_))
+        ));
+
+        let mut message = WebhookMessage::batch(&[]);
+        message.blocks = vec![json!({ "type": "divider" }); MAX_BLOCKS];
+        assert_eq!(message.validate(), Ok(()));
+        message.blocks.push(json!({ "type": "divider" }));
+        assert_eq!(message.validate(), Err(ValidationError::TooManyBlocks(MAX_BLOCKS + 1)));
+
+        let mut message = WebhookMessage::batch(&[]);
+        message.text = "x".repeat(MAX_PAYLOAD_SIZE);
+        assert!(matches!(message.validate(), Err(ValidationError::PayloadTooLarge(_))));
+    }
+
+    #[test]
+    fn auto_upgrade_note() {
+        const NOTE: &str = "Auto-upgrade is not configured for this cluster. Consider enabling it.";
+
+        for test in test_messages() {
+            if test.message.attributes.payload.as_upgrade_available_event().is_none() {
+                continue;
+            }
+
+            let mut message = test.message;
+            for (cluster_auto_upgrade, expected) in
+                [(None, false), (Some(true), false), (Some(false), true)]
+            {
+                message.attributes.cluster_auto_upgrade = cluster_auto_upgrade;
+                let slack_message =
+                    WebhookMessage::new(&message, &Config::default(), &Annotations::default());
+                let note = slack_message.blocks.iter().any(|block| block["text"]["text"] == NOTE);
+
+                assert_eq!(note, expected, "{cluster_auto_upgrade:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn metadata() {
+        for test in test_messages() {
+            if test.message.is_invalid() {
+                continue;
+            }
+            let attr = &test.message.attributes;
+            let message =
+                WebhookMessage::new(&test.message, &Config::default(), &Annotations::default());
+            let metadata = serde_json::to_value(&message).unwrap()["metadata"].clone();
+
+            assert!(metadata["event_type"].as_str().unwrap().starts_with("gke_"));
+            assert_eq!(metadata["event_payload"]["cluster_name"], attr.cluster_name);
+            assert_eq!(metadata["event_payload"]["project_id"], attr.project_id);
+            assert_eq!(metadata["event_payload"]["payload_type"], attr.payload.to_string());
         }
     }
 }
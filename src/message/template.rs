@@ -0,0 +1,176 @@
+use tera::{Context, Tera};
+use tracing::warn;
+
+use super::{attributes::payload::Payload, Message};
+use crate::error::AppError;
+use crate::locale::Locale;
+
+const SECURITY: &str = "security";
+const UPGRADE: &str = "upgrade";
+const UPGRADE_AVAILABLE: &str = "upgrade_available";
+
+/// Renders the plain text of Slack messages from the Tera templates in
+/// `SLACK_TEXT_TEMPLATE_SECURITY`, `SLACK_TEXT_TEMPLATE_UPGRADE` and
+/// `SLACK_TEXT_TEMPLATE_UPGRADE_AVAILABLE`. Messages without a template use
+/// `Message::plain_text`.
+///
+/// Templates can use `cluster_name`, `cluster_location`, `project_id`,
+/// `project_name`, `type_url`, `resource_uri`, `resource_url`,
+/// `plain_text` and `markdown`, plus the fields of the payload.
+#[derive(Clone, Debug, Default)]
+pub struct TemplateEngine {
+    tera: Tera,
+}
+
+impl TemplateEngine {
+    /// Compiles the templates, failing with the name and error of the first
+    /// invalid one.
+    pub fn new(
+        security: Option<String>,
+        upgrade: Option<String>,
+        upgrade_available: Option<String>,
+    ) -> Result<Self, AppError> {
+        let mut tera = Tera::default();
+        for (name, env, template) in [
+            (SECURITY, "SLACK_TEXT_TEMPLATE_SECURITY", security),
+            (UPGRADE, "SLACK_TEXT_TEMPLATE_UPGRADE", upgrade),
+            (UPGRADE_AVAILABLE, "SLACK_TEXT_TEMPLATE_UPGRADE_AVAILABLE", upgrade_available),
+        ] {
+            if let Some(template) = template {
+                tera.add_raw_template(name, &template).map_err(|err| {
+                    AppError::InvalidConfig(format!("{env} is not a valid template: {err:?}"))
+                })?;
+            }
+        }
+
+        Ok(Self { tera })
+    }
+
+    /// Renders the template for the message's payload type. Falls back to
+    /// `Message::plain_text_localized` when there is no template or rendering
+    /// fails, e.g. because the template refers to an unknown field.
+    pub fn render_plain_text(&self, message: &Message, locale: Locale) -> String {
+        let name = match &message.attributes.payload {
+            Payload::SecurityBulletinEvent(_) => SECURITY,
+            Payload::UpgradeEvent(_) => UPGRADE,
+            Payload::UpgradeAvailableEvent(_) => UPGRADE_AVAILABLE,
+            _ => return message.plain_text_localized(locale),
+        };
+
+        if !self.tera.get_template_names().any(|template| template == name) {
+            return message.plain_text_localized(locale);
+        }
+
+        self.tera.render(name, &context(message)).unwrap_or_else(|err| {
+            warn!(template = name, "failed to render Slack text template: {err:?}");
+            message.plain_text_localized(locale)
+        })
+    }
+}
+
+fn context(message: &Message) -> Context {
+    let attr = &message.attributes;
+    let mut context = Context::new();
+    context.insert("cluster_name", &attr.cluster_name);
+    context.insert("cluster_location", &attr.cluster_location);
+    context.insert("project_id", &attr.project_id);
+    context.insert("project_name", &attr.project_name());
+    context.insert("type_url", &attr.type_url);
+    context.insert("resource_uri", &attr.resource_uri());
+    context.insert("resource_url", &attr.resource_url());
+    context.insert("plain_text", &message.plain_text());
+    context.insert("markdown", &message.markdown());
+
+    match &attr.payload {
+        Payload::SecurityBulletinEvent(p) => {
+            context.insert("bulletin_id", &p.bulletin_id);
+            context.insert("bulletin_uri", &p.bulletin_uri);
+            context.insert("brief_description", &p.brief_description);
+            context.insert("severity", &p.severity);
+            context.insert("cve_ids", &p.cve_ids);
+            context.insert("patched_versions", &p.patched_versions);
+            context.insert("suggested_upgrade_target", &p.suggested_upgrade_target);
+            context.insert("resource_type_affected", &p.resource_type_affected());
+            context.insert("manual_steps_required", &p.manual_steps_required);
+        }
+        Payload::UpgradeEvent(p) => {
+            context.insert("current_version", &p.current_version);
+            context.insert("target_version", &p.target_version);
+            context.insert("node_pool_name", &p.node_pool_name());
+            context.insert("operation", &p.operation);
+            context.insert("operation_start_time", &p.operation_start_time);
+        }
+        Payload::UpgradeAvailableEvent(p) => {
+            context.insert("version", &p.version);
+            context.insert("release_channel", &p.release_channel.to_string());
+            context.insert("node_pool_name", &p.node_pool_name());
+        }
+        _ => {}
+    }
+
+    context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::tests::test_messages;
+
+    fn render(engine: &TemplateEngine, name: &str) -> String {
+        let test = test_messages().into_iter().find(|test| test.name == name).unwrap();
+        engine.render_plain_text(&test.message, Locale::En)
+    }
+
+    #[test]
+    fn render_plain_text() {
+        let engine = TemplateEngine::new(
+            Some("[{{ severity }}] {{ bulletin_id }} on {{ cluster_name }}".to_string()),
+            Some("{{ cluster_name }}: {{ current_version }} → {{ target_version }}".to_string()),
+            Some("{{ version }} available in {{ release_channel | lower }}".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            render(&engine, "SecurityBulletinEvent:ControlPlane"),
+            "[Medium] GCP-2022-005 on test-cluster"
+        );
+        assert_eq!(
+            render(&engine, "UpgradeEvent:ControlPlane"),
+            "test-cluster: 1.22.4-gke.1501 → 1.22.6-gke.300"
+        );
+        assert_eq!(
+            render(&engine, "UpgradeAvailableEvent:ControlPlane"),
+            "1.22.6-gke.300 available in rapid"
+        );
+        assert_eq!(
+            render(&engine, "ClusterStatusChangeEvent:Error"),
+            "test-cluster status changed to ERROR"
+        );
+    }
+
+    #[test]
+    fn fallback() {
+        let engine = TemplateEngine::default();
+        assert_eq!(
+            render(&engine, "UpgradeEvent:ControlPlane"),
+            "test-cluster control plane is upgrading to version 1.22.6-gke.300"
+        );
+
+        // Unknown fields fail at render time
+        let engine =
+            TemplateEngine::new(None, Some("{{ no_such_field }}".to_string()), None).unwrap();
+        assert_eq!(
+            render(&engine, "UpgradeEvent:ControlPlane"),
+            "test-cluster control plane is upgrading to version 1.22.6-gke.300"
+        );
+    }
+
+    #[test]
+    fn invalid_template() {
+        let err = TemplateEngine::new(Some("{{ bulletin_id".to_string()), None, None).unwrap_err();
+        let AppError::InvalidConfig(err) = err else {
+            panic!("expected AppError::InvalidConfig, got {err:?}");
+        };
+        assert!(err.starts_with("SLACK_TEXT_TEMPLATE_SECURITY is not a valid template"), "{err}");
+    }
+}
@@ -0,0 +1,271 @@
+use axum::http::StatusCode;
+use axum::Json;
+use std::time::Duration;
+
+use axum::extract::{Query, State};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::{FromRow, QueryBuilder, Sqlite};
+use tracing::{debug, error};
+
+use super::Message;
+use crate::error::AppError;
+use crate::AppState;
+
+/// The number of records returned by `GET /api/v1/history` by default, and
+/// at most.
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+
+/// An attempt to post a message to a notification service.
+#[derive(Clone, Debug, FromRow, PartialEq, Serialize)]
+pub struct NotificationRecord {
+    /// Assigned when the record is inserted.
+    pub id: i64,
+    pub message_id: String,
+    pub cluster_name: String,
+    pub event_type: String,
+    pub notified_at: DateTime<Utc>,
+
+    /// The service the message was posted to, e.g. `slack`.
+    pub destination: String,
+    pub success: bool,
+
+    /// The response of the service, or the error when posting failed.
+    pub response: String,
+}
+
+impl NotificationRecord {
+    pub fn new(message: &Message, destination: &str, result: &Result<String, AppError>) -> Self {
+        NotificationRecord {
+            id: 0,
+            message_id: message.message_id().to_string(),
+            cluster_name: message.attributes.cluster_name.clone(),
+            event_type: message.attributes.event_type().to_string(),
+            notified_at: Utc::now(),
+            destination: destination.to_string(),
+            success: result.is_ok(),
+            response: match result {
+                Ok(response) => response.clone(),
+                Err(err) => err.to_string(),
+            },
+        }
+    }
+}
+
+/// The filters of `GET /api/v1/history`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Only records for this cluster.
+    pub cluster: Option<String>,
+
+    /// Only records of notifications at or after this time.
+    pub since: Option<DateTime<Utc>>,
+
+    pub limit: Option<u32>,
+}
+
+/// The audit trail of notifications, persisted to a SQLite database.
+#[derive(Clone, Debug)]
+pub struct NotificationHistory {
+    pool: SqlitePool,
+}
+
+impl NotificationHistory {
+    /// Opens the database at `path`, creating it when it doesn't exist, and
+    /// runs the migrations.
+    pub async fn open(path: &str) -> Result<Self, AppError> {
+        let options = SqliteConnectOptions::new().filename(path).create_if_missing(true);
+        Self::connect(options, SqlitePoolOptions::new()).await
+    }
+
+    /// Opens a new in-memory database, which only lives as long as its
+    /// single connection.
+    #[cfg(test)]
+    pub async fn in_memory() -> Result<Self, AppError> {
+        let options = SqliteConnectOptions::new().in_memory(true);
+        let pool_options = SqlitePoolOptions::new().max_connections(1).idle_timeout(None);
+        Self::connect(options, pool_options.max_lifetime(None)).await
+    }
+
+    async fn connect(
+        options: SqliteConnectOptions,
+        pool_options: SqlitePoolOptions,
+    ) -> Result<Self, AppError> {
+        let pool = pool_options.connect_with(options).await?;
+        sqlx::migrate!().run(&pool).await.map_err(|err| sqlx::Error::Migrate(Box::new(err)))?;
+        Ok(Self { pool })
+    }
+
+    /// Inserts the record, returning its `id`.
+    pub async fn insert(&self, record: &NotificationRecord) -> Result<i64, AppError> {
+        let result = sqlx::query(
+            "INSERT INTO notification_records \
+             (message_id, cluster_name, event_type, notified_at, destination, success, response) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&record.message_id)
+        .bind(&record.cluster_name)
+        .bind(&record.event_type)
+        .bind(record.notified_at)
+        .bind(&record.destination)
+        .bind(record.success)
+        .bind(&record.response)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Records an attempt to post the message, logging when that fails
+    /// rather than failing the notification.
+    pub async fn record(
+        &self,
+        message: &Message,
+        destination: &str,
+        result: &Result<String, AppError>,
+    ) {
+        if let Err(err) = self.insert(&NotificationRecord::new(message, destination, result)).await
+        {
+            error!(destination, "recording notification history failed: {err}");
+        }
+    }
+
+    /// Deletes the records of notifications before `before`, returning the
+    /// number of deleted records.
+    pub async fn prune(&self, before: DateTime<Utc>) -> Result<u64, AppError> {
+        let result = sqlx::query("DELETE FROM notification_records WHERE notified_at < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Spawns a background task deleting records older than `retention`
+    /// every `interval`.
+    pub fn spawn_pruner(&self, retention: Duration, interval: Duration) {
+        let history = self.clone();
+        let retention = TimeDelta::from_std(retention).unwrap_or(TimeDelta::MAX);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                match history.prune(Utc::now() - retention).await {
+                    Ok(deleted) => debug!(deleted, "pruned notification history"),
+                    Err(err) => error!("pruning notification history failed: {err}"),
+                }
+            }
+        });
+    }
+
+    /// The records matching the query, most recent first.
+    pub async fn query(&self, query: &HistoryQuery) -> Result<Vec<NotificationRecord>, AppError> {
+        let mut builder: QueryBuilder<Sqlite> =
+            QueryBuilder::new("SELECT * FROM notification_records WHERE 1 = 1");
+        if let Some(cluster) = &query.cluster {
+            builder.push(" AND cluster_name = ").push_bind(cluster);
+        }
+        if let Some(since) = query.since {
+            builder.push(" AND notified_at >= ").push_bind(since);
+        }
+        builder
+            .push(" ORDER BY notified_at DESC, id DESC LIMIT ")
+            .push_bind(query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT));
+
+        Ok(builder.build_query_as().fetch_all(&self.pool).await?)
+    }
+}
+
+/// Lists the notification history (`GET /api/v1/history`), filtered by the
+/// `cluster`, `since` (RFC 3339) and `limit` query parameters.
+pub async fn list_history(
+    State(state): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> (StatusCode, Json<Value>) {
+    let Some(history) = &state.history else {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": "history_disabled" })));
+    };
+
+    match history.query(&query).await {
+        Ok(records) => (StatusCode::OK, Json(json!({ "records": records }))),
+        Err(err) => {
+            error!("querying notification history failed: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": "query_failed" })))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::tests::test_messages;
+    use chrono::TimeDelta;
+
+    fn record(cluster_name: &str, notified_at: DateTime<Utc>) -> NotificationRecord {
+        let test = test_messages().into_iter().find(|test| !test.message.is_invalid()).unwrap();
+        NotificationRecord {
+            cluster_name: cluster_name.to_string(),
+            notified_at,
+            ..NotificationRecord::new(&test.message, "slack", &Ok("ok".to_string()))
+        }
+    }
+
+    #[test]
+    fn new() {
+        let test = test_messages().into_iter().find(|test| !test.message.is_invalid()).unwrap();
+        let error = AppError::HttpPostFailed { status: 403, body: "Forbidden".to_string() };
+        let record = NotificationRecord::new(&test.message, "datadog", &Err(error));
+
+        assert_eq!(record.message_id, test.message.message_id());
+        assert_eq!(record.cluster_name, "test-cluster");
+        assert_eq!(record.event_type, test.message.attributes.event_type());
+        assert_eq!(record.destination, "datadog");
+        assert!(!record.success);
+        assert_eq!(record.response, "HTTP 403: Forbidden");
+    }
+
+    #[tokio::test]
+    async fn query() {
+        let history = NotificationHistory::in_memory().await.unwrap();
+        let now = Utc::now();
+        let mut records = vec![];
+        for (cluster_name, age) in [("cluster-a", 3), ("cluster-b", 2), ("cluster-a", 1)] {
+            let mut record = record(cluster_name, now - TimeDelta::hours(age));
+            record.id = history.insert(&record).await.unwrap();
+            records.push(record);
+        }
+
+        let all = history.query(&HistoryQuery::default()).await.unwrap();
+        assert_eq!(all, [records[2].clone(), records[1].clone(), records[0].clone()]);
+
+        let query = HistoryQuery { cluster: Some("cluster-a".to_string()), ..Default::default() };
+        assert_eq!(history.query(&query).await.unwrap(), [records[2].clone(), records[0].clone()]);
+
+        let query =
+            HistoryQuery { since: Some(now - TimeDelta::minutes(150)), ..Default::default() };
+        assert_eq!(history.query(&query).await.unwrap(), [records[2].clone(), records[1].clone()]);
+
+        let query = HistoryQuery { limit: Some(1), ..Default::default() };
+        assert_eq!(history.query(&query).await.unwrap(), [records[2].clone()]);
+    }
+
+    #[tokio::test]
+    async fn prune() {
+        let history = NotificationHistory::in_memory().await.unwrap();
+        let now = Utc::now();
+        let mut records = vec![];
+        for age in [48, 25, 1] {
+            let mut record = record("cluster-a", now - TimeDelta::hours(age));
+            record.id = history.insert(&record).await.unwrap();
+            records.push(record);
+        }
+
+        assert_eq!(history.prune(now - TimeDelta::days(1)).await.unwrap(), 2);
+        let all = history.query(&HistoryQuery::default()).await.unwrap();
+        assert_eq!(all, [records[2].clone()]);
+    }
+}
@@ -0,0 +1,85 @@
+use axum::http::HeaderMap;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing::Span;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+const SERVICE_NAME: &str = "gke-cluster-notifications";
+
+/// Creates a tracer provider exporting spans over OTLP/HTTP to
+/// `OTEL_EXPORTER_OTLP_ENDPOINT`, `None` when it isn't set. The provider
+/// should be shut down before exiting, flushing the remaining spans.
+pub fn tracer_provider() -> Option<SdkTracerProvider> {
+    std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT")?;
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .build()
+        .expect("OTEL_EXPORTER_OTLP_ENDPOINT should be a valid endpoint");
+
+    Some(
+        SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+            .build(),
+    )
+}
+
+/// A `tracing` layer exporting spans through the provider.
+pub fn layer<S>(provider: &SdkTracerProvider) -> OpenTelemetryLayer<S, SdkTracer>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME))
+}
+
+/// Makes the trace in the W3C `traceparent` and `tracestate` headers, which
+/// Pub/Sub push subscriptions send when tracing is enabled, the parent of
+/// `span`. Without the headers, `span` starts a new trace.
+pub fn set_parent_from_headers(span: &Span, headers: &HeaderMap) {
+    let context = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    let _ = span.set_parent(context);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{TraceContextExt, TraceId};
+    use tracing_subscriber::prelude::*;
+
+    fn trace_id(headers: &HeaderMap) -> TraceId {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry().with(layer(&provider));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handler");
+            set_parent_from_headers(&span, headers);
+            span.context().span().span_context().trace_id()
+        })
+    }
+
+    #[test]
+    fn propagation() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".parse().unwrap(),
+        );
+        headers.insert("tracestate", "congo=t61rcWkgMzE".parse().unwrap());
+
+        assert_eq!(
+            trace_id(&headers),
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap()
+        );
+
+        let trace_id = trace_id(&HeaderMap::new());
+        assert_ne!(trace_id, TraceId::INVALID, "a new trace is started");
+        assert_ne!(trace_id, TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap());
+    }
+}
@@ -0,0 +1,439 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use lru::LruCache;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::error::AppError;
+use crate::message::{Message, PubSubMessage};
+use crate::AppState;
+
+const PUBSUB_ENDPOINT: &str = "https://pubsub.googleapis.com";
+const PUBSUB_SCOPE: &str = "https://www.googleapis.com/auth/pubsub";
+const MAX_MESSAGES: u32 = 10;
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+const ATTEMPTS_CAPACITY: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct PullResponse {
+    received_messages: Vec<ReceivedMessage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ReceivedMessage {
+    ack_id: String,
+    message: Value,
+    /// Only set by Pub/Sub when the subscription has a dead-letter policy.
+    delivery_attempt: u32,
+}
+
+/// Pulls GKE Cluster Notifications from a Pub/Sub subscription for
+/// deployments where a push subscription can't reach the service.
+///
+/// Messages are only acknowledged once they have been processed. When posting
+/// to Slack fails they're nacked to be redelivered, until they've been
+/// delivered `PUBSUB_MAX_DELIVERY_ATTEMPTS` times, after which they're
+/// published to `PUBSUB_DEAD_LETTER_TOPIC` (or dropped) and acknowledged.
+/// Messages posted by the batcher (`BATCH_WINDOW_MS`) are acknowledged before
+/// they're posted, so they aren't redelivered.
+///
+/// When `PUBSUB_EMULATOR_HOST` is set, requests are sent to the emulator
+/// without authentication. These settings are read from the current
+/// `Config`, so they're reloaded on SIGHUP.
+pub struct PubSubPuller {
+    subscription: String,
+    /// Delivery attempts of recently failed messages by `message_id`, for
+    /// subscriptions without a dead-letter policy.
+    attempts: Mutex<LruCache<String, u32>>,
+}
+
+impl PubSubPuller {
+    /// Creates a puller for `subscription_id`, which may either be a full
+    /// `projects/{project}/subscriptions/{id}` path or an id within the
+    /// project configured via `GCP_PROJECT`.
+    pub fn new(subscription_id: String) -> Result<Self, AppError> {
+        Ok(Self {
+            subscription: resource_path("subscriptions", subscription_id)?,
+            attempts: Mutex::new(LruCache::new(ATTEMPTS_CAPACITY)),
+        })
+    }
+
+    /// Pulls and processes messages until `shutdown` is cancelled.
+    pub async fn run(self, state: AppState, shutdown: CancellationToken) {
+        info!(subscription = self.subscription, "starting pub/sub puller");
+
+        loop {
+            let received = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                res = self.pull(&state) => res,
+            };
+
+            match received {
+                Ok(messages) => self.process(&state, messages).await,
+                Err(err) => {
+                    error!(
+                        subscription = self.subscription,
+                        "pull from subscription failed: {err}"
+                    );
+                    tokio::select! {
+                        _ = shutdown.cancelled() => break,
+                        _ = tokio::time::sleep(RETRY_DELAY) => {}
+                    }
+                }
+            }
+        }
+
+        info!(subscription = self.subscription, "stopped pub/sub puller");
+    }
+
+    async fn process(&self, state: &AppState, messages: Vec<ReceivedMessage>) {
+        let mut ack_ids = vec![];
+        let mut nack_ids = vec![];
+
+        for received in messages {
+            let message_id = received.message["messageId"].as_str().unwrap_or_default().to_string();
+
+            let delivered =
+                match serde_json::from_value::<Message>(normalize(received.message.clone())) {
+                    Ok(message) => {
+                        let subscription = self.subscription.clone();
+                        crate::process(state, PubSubMessage { message, subscription }).await
+                    }
+                    Err(err) => {
+                        warn!(subscription = self.subscription, "invalid pub/sub message: {err}");
+                        true
+                    }
+                };
+            if delivered {
+                self.attempts.lock().unwrap().pop(&message_id);
+                ack_ids.push(received.ack_id);
+                continue;
+            }
+
+            let attempts = self.delivery_attempts(&message_id, received.delivery_attempt);
+            if attempts < state.config.load().pubsub_max_delivery_attempts {
+                warn!(message_id, attempts, "processing failed, message will be redelivered");
+                nack_ids.push(received.ack_id);
+                continue;
+            }
+
+            match self.dead_letter(state, &received.message).await {
+                Ok(()) => {
+                    self.attempts.lock().unwrap().pop(&message_id);
+                    ack_ids.push(received.ack_id);
+                }
+                Err(err) => {
+                    error!(message_id, "publishing to the dead-letter topic failed: {err}");
+                    nack_ids.push(received.ack_id);
+                }
+            }
+        }
+
+        if !ack_ids.is_empty() {
+            let body = json!({ "ackIds": ack_ids });
+            if let Err(err) = self.call(state, &self.subscription, "acknowledge", body).await {
+                error!(subscription = self.subscription, "acknowledge failed: {err}");
+            }
+        }
+
+        // A deadline of 0 makes the messages available for redelivery right away
+        if !nack_ids.is_empty() {
+            let body = json!({ "ackIds": nack_ids, "ackDeadlineSeconds": 0 });
+            if let Err(err) = self.call(state, &self.subscription, "modifyAckDeadline", body).await
+            {
+                error!(subscription = self.subscription, "nack failed: {err}");
+            }
+        }
+    }
+
+    /// Counts a failed delivery of `message_id`, preferring the count kept by
+    /// Pub/Sub when the subscription has a dead-letter policy.
+    fn delivery_attempts(&self, message_id: &str, delivery_attempt: u32) -> u32 {
+        let mut attempts = self.attempts.lock().unwrap();
+        let count = attempts.get_or_insert_mut(message_id.to_string(), || 0);
+        *count = (*count + 1).max(delivery_attempt);
+        *count
+    }
+
+    /// Publishes a message which exceeded `PUBSUB_MAX_DELIVERY_ATTEMPTS` to
+    /// the dead-letter topic, or drops it when there is none.
+    async fn dead_letter(&self, state: &AppState, message: &Value) -> Result<(), AppError> {
+        let config = state.config.load();
+        let message_id = message["messageId"].as_str().unwrap_or_default();
+        let Some(topic) = &config.pubsub_dead_letter_topic else {
+            error!(
+                message_id,
+                attempts = config.pubsub_max_delivery_attempts,
+                "dropping message after too many delivery attempts"
+            );
+            return Ok(());
+        };
+
+        let message = json!({
+            "data": message["data"],
+            "attributes": message.get("attributes").cloned().unwrap_or_else(|| json!({})),
+        });
+        self.call(state, topic, "publish", json!({ "messages": [message] })).await?;
+        warn!(message_id, topic, "published message to the dead-letter topic");
+        Ok(())
+    }
+
+    async fn pull(&self, state: &AppState) -> Result<Vec<ReceivedMessage>, AppError> {
+        let body = json!({ "maxMessages": MAX_MESSAGES });
+        let body = self.call(state, &self.subscription, "pull", body).await?;
+        let response = serde_json::from_str::<PullResponse>(&body)?;
+        Ok(response.received_messages)
+    }
+
+    async fn call(
+        &self,
+        state: &AppState,
+        resource: &str,
+        method: &str,
+        body: Value,
+    ) -> Result<String, AppError> {
+        let emulator_host = state.config.load().pubsub_emulator_host.clone();
+        let endpoint = match &emulator_host {
+            Some(host) => format!("http://{host}"),
+            None => PUBSUB_ENDPOINT.to_string(),
+        };
+        let mut request = state
+            .http
+            .post(format!("{endpoint}/v1/{resource}:{method}"))
+            .header("Content-Type", "application/json")
+            .body(body.to_string());
+
+        if emulator_host.is_none() {
+            let token = state.token_provider().await?.token(&[PUBSUB_SCOPE]).await?;
+            request = request.bearer_auth(token.as_str());
+        }
+
+        let resp = request.send().await?;
+        let status = resp.status();
+        let text = resp.text().await?;
+
+        match status.is_success() {
+            true => Ok(text),
+            false => Err(AppError::HttpPostFailed { status: status.as_u16(), body: text }),
+        }
+    }
+}
+
+/// Expands an id within the project configured via `GCP_PROJECT` to a full
+/// `projects/{project}/{collection}/{id}` path.
+pub fn resource_path(collection: &str, id: String) -> Result<String, AppError> {
+    if id.starts_with("projects/") {
+        return Ok(id);
+    }
+
+    let project = std::env::var("GCP_PROJECT").map_err(|_| {
+        let kind = collection.trim_end_matches('s');
+        AppError::InvalidConfig(format!("GCP_PROJECT is required for a relative {kind} id"))
+    })?;
+    Ok(format!("projects/{project}/{collection}/{id}"))
+}
+
+/// Pulled messages only use camelCase field names, while push deliveries
+/// include both forms. Copy the camelCase fields so the existing `Message`
+/// deserialization can be reused.
+fn normalize(mut message: Value) -> Value {
+    if let Some(fields) = message.as_object_mut() {
+        for (from, to) in [("messageId", "message_id"), ("publishTime", "publish_time")] {
+            if let Some(value) = fields.remove(from) {
+                fields.entry(to).or_insert(value);
+            }
+        }
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SUBSCRIPTION: &str = "projects/test-project/subscriptions/test-subscription";
+    const MAX_DELIVERY_ATTEMPTS: u32 = 2;
+
+    fn puller() -> PubSubPuller {
+        PubSubPuller::new(SUBSCRIPTION.to_string()).unwrap()
+    }
+
+    /// Pulls from `server` as an emulator and posts to its `/slack` webhook.
+    fn pubsub_config(server: &MockServer) -> Config {
+        Config {
+            slack_webhooks: vec![format!("{}/slack", server.uri())],
+            pubsub_emulator_host: Some(server.address().to_string()),
+            pubsub_max_delivery_attempts: MAX_DELIVERY_ATTEMPTS,
+            ..Config::default()
+        }
+    }
+
+    fn received_message(delivery_attempt: u32) -> ReceivedMessage {
+        ReceivedMessage {
+            ack_id: "ack-1".to_string(),
+            message: json!({
+                "attributes": {
+                    "cluster_location": "us-central1",
+                    "cluster_name": "test-cluster",
+                    "payload": "{\"resourceType\":\"MASTER\",\"version\":\"1.22.6-gke.300\"}",
+                    "project_id": "0123456789",
+                    "type_url": "type.googleapis.com/google.container.v1beta1.UpgradeAvailableEvent",
+                },
+                "data": "bG9yZW0gaXBzdW0=",
+                "messageId": "1722065266338564",
+            }),
+            delivery_attempt,
+        }
+    }
+
+    async fn slack_state(server: &MockServer, status: u16, config: Config) -> AppState {
+        Mock::given(method("POST"))
+            .and(path("/slack"))
+            .respond_with(ResponseTemplate::new(status))
+            .mount(server)
+            .await;
+        AppState::new(config)
+    }
+
+    async fn calls(server: &MockServer, method: &str) -> Vec<Value> {
+        let suffix = format!(":{method}");
+        let requests = server.received_requests().await.unwrap();
+        requests
+            .iter()
+            .filter(|request| request.url.path().ends_with(&suffix))
+            .map(|request| serde_json::from_slice(&request.body).unwrap())
+            .collect()
+    }
+
+    async fn mount_pubsub(server: &MockServer) {
+        Mock::given(method("POST"))
+            .and(path(format!("/v1/{SUBSCRIPTION}:acknowledge")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/v1/{SUBSCRIPTION}:modifyAckDeadline")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn ack_when_delivered() {
+        let server = MockServer::start().await;
+        mount_pubsub(&server).await;
+        let state = slack_state(&server, 200, pubsub_config(&server)).await;
+
+        puller().process(&state, vec![received_message(0)]).await;
+
+        assert_eq!(calls(&server, "acknowledge").await, [json!({ "ackIds": ["ack-1"] })]);
+        assert!(calls(&server, "modifyAckDeadline").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn nack_when_slack_fails() {
+        let server = MockServer::start().await;
+        mount_pubsub(&server).await;
+        let state = slack_state(&server, 500, pubsub_config(&server)).await;
+        let puller = puller();
+
+        puller.process(&state, vec![received_message(0)]).await;
+
+        assert!(calls(&server, "acknowledge").await.is_empty());
+        assert_eq!(
+            calls(&server, "modifyAckDeadline").await,
+            [json!({ "ackIds": ["ack-1"], "ackDeadlineSeconds": 0 })]
+        );
+
+        // The redelivered message is processed again rather than skipped as a
+        // duplicate, and dropped once it reached the maximum delivery attempts
+        puller.process(&state, vec![received_message(0)]).await;
+        assert_eq!(calls(&server, "acknowledge").await.len(), 1);
+        let requests = server.received_requests().await.unwrap();
+        let slack_posts = requests.iter().filter(|request| request.url.path() == "/slack").count();
+        assert_eq!(slack_posts, MAX_DELIVERY_ATTEMPTS as usize);
+    }
+
+    #[tokio::test]
+    async fn redelivery_only_retries_failed_backends() {
+        let server = MockServer::start().await;
+        mount_pubsub(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/events"))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/slack"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        let config = Config {
+            datadog_api_key: Some("test-key".to_string()),
+            datadog_api_url: server.uri(),
+            ..pubsub_config(&server)
+        };
+        let state = AppState::new(config);
+        let puller = puller();
+
+        for _ in 0..MAX_DELIVERY_ATTEMPTS {
+            puller.process(&state, vec![received_message(0)]).await;
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        let slack_posts = requests.iter().filter(|request| request.url.path() == "/slack").count();
+        assert_eq!(slack_posts, MAX_DELIVERY_ATTEMPTS as usize);
+    }
+
+    #[tokio::test]
+    async fn dead_letter() {
+        let server = MockServer::start().await;
+        mount_pubsub(&server).await;
+        let config = Config {
+            pubsub_dead_letter_topic: Some("projects/test-project/topics/dead-letter".to_string()),
+            ..pubsub_config(&server)
+        };
+        let state = slack_state(&server, 500, config).await;
+        Mock::given(method("POST"))
+            .and(path("/v1/projects/test-project/topics/dead-letter:publish"))
+            .and(body_partial_json(json!({
+                "messages": [{
+                    "data": "bG9yZW0gaXBzdW0=",
+                    "attributes": { "cluster_name": "test-cluster" },
+                }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "messageIds": ["1"] })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        puller().process(&state, vec![received_message(2)]).await;
+
+        assert_eq!(calls(&server, "acknowledge").await, [json!({ "ackIds": ["ack-1"] })]);
+        assert!(calls(&server, "modifyAckDeadline").await.is_empty());
+    }
+
+    #[test]
+    fn pulled_message() {
+        let message = serde_json::from_value::<Message>(normalize(json!({
+            "attributes": { "cluster_name": "test-cluster" },
+            "data": "bG9yZW0gaXBzdW0=",
+            "messageId": "1722065266338564",
+            "publishTime": "2023-01-13T19:51:24.884Z",
+        })))
+        .unwrap();
+
+        assert_eq!(message.attributes.cluster_name, "test-cluster");
+        assert_eq!(message.log_entry(), "Empty or invalid payload: lorem ipsum");
+    }
+}
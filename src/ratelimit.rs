@@ -0,0 +1,121 @@
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use lru::LruCache;
+use tracing::warn;
+
+use crate::AppState;
+
+const CAPACITY: NonZeroUsize = NonZeroUsize::new(10_000).unwrap();
+
+/// A token bucket per client IP address, allowing bursts of up to `rps`
+/// requests which are refilled at `rps` requests per second. A limit of 0
+/// allows all requests.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<LruCache<IpAddr, Bucket>>>,
+    rps: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(rps: u32) -> Self {
+        Self { buckets: Arc::new(Mutex::new(LruCache::new(CAPACITY))), rps }
+    }
+
+    /// Takes a token from the bucket of `ip`, returning the number of
+    /// seconds until a token is available when the bucket is empty.
+    pub fn check(&self, ip: IpAddr) -> Result<(), u64> {
+        self.check_at(ip, Instant::now())
+    }
+
+    fn check_at(&self, ip: IpAddr, now: Instant) -> Result<(), u64> {
+        if self.rps == 0 {
+            return Ok(());
+        }
+
+        let rps = f64::from(self.rps);
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.get_or_insert_mut(ip, || Bucket { tokens: rps, refilled: now });
+        let elapsed = now.saturating_duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rps).min(rps);
+        bucket.refilled = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / rps).ceil().max(1.0) as u64)
+        }
+    }
+}
+
+/// Limits the requests per client IP address to `RATE_LIMIT_RPS`, responding
+/// with HTTP 429 and a `Retry-After` header once the limit is exceeded, which
+/// makes Pub/Sub back off instead of pushing a large backlog all at once.
+pub async fn rate_limit(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    // Only missing when the router is called directly, as in tests; sharing
+    // a single bucket between unknown clients would throttle all of them
+    let Some(ConnectInfo(addr)) = req.extensions().get::<ConnectInfo<SocketAddr>>() else {
+        return next.run(req).await;
+    };
+    let ip = addr.ip();
+
+    if let Err(retry_after) = state.rate_limiter.check(ip) {
+        warn!(%ip, "rate limit exceeded, retry after {retry_after}s");
+        return (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, retry_after.to_string())])
+            .into_response();
+    }
+
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    #[test]
+    fn token_bucket() {
+        let limiter = RateLimiter::new(2);
+        let now = Instant::now();
+
+        assert_eq!(limiter.check_at(IP, now), Ok(()));
+        assert_eq!(limiter.check_at(IP, now), Ok(()));
+        assert_eq!(limiter.check_at(IP, now), Err(1));
+        assert_eq!(limiter.check_at(IpAddr::V4(Ipv4Addr::LOCALHOST), now), Ok(()));
+
+        // Half a second refills one token
+        assert_eq!(limiter.check_at(IP, now + Duration::from_millis(500)), Ok(()));
+        assert_eq!(limiter.check_at(IP, now + Duration::from_millis(500)), Err(1));
+
+        // The bucket never holds more than `rps` tokens
+        let later = now + Duration::from_secs(60);
+        assert_eq!(limiter.check_at(IP, later), Ok(()));
+        assert_eq!(limiter.check_at(IP, later), Ok(()));
+        assert_eq!(limiter.check_at(IP, later), Err(1));
+    }
+
+    #[test]
+    fn disabled() {
+        let limiter = RateLimiter::new(0);
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert_eq!(limiter.check_at(IP, now), Ok(()));
+        }
+    }
+}
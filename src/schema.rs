@@ -0,0 +1,42 @@
+use axum::http::header;
+use axum::response::IntoResponse;
+
+/// A JSON Schema of the Pub/Sub push messages accepted by `POST /`, which
+/// helps constructing test payloads. The payload attribute is a JSON string,
+/// described by a `contentSchema` depending on the `type_url`.
+const SCHEMA: &str = include_str!("schema.json");
+
+/// Returns the JSON Schema of the Pub/Sub messages (`GET /api/v1/schema`).
+pub async fn get_schema() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], SCHEMA)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::attributes::payload::Payload;
+    use crate::message::tests::test_messages;
+    use serde_json::Value;
+
+    #[test]
+    fn payload_fields() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let type_urls =
+            schema["$defs"]["Attributes"]["properties"]["type_url"]["examples"].as_array().unwrap();
+
+        for test in test_messages() {
+            let attr = &test.message.attributes;
+            if matches!(attr.payload, Payload::None | Payload::UnknownType(_)) {
+                continue;
+            }
+            assert!(type_urls.contains(&Value::from(attr.type_url.as_str())), "{}", test.name);
+
+            // Every field of the payload types should be described
+            let properties = &schema["$defs"][attr.payload.to_string()]["properties"];
+            let payload = serde_json::to_value(&attr.payload).unwrap();
+            for field in payload.as_object().unwrap().keys() {
+                assert!(properties.get(field).is_some(), "{}: {field}", test.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::{fmt::Display, sync::Arc};
+
+use base64::prelude::*;
+use gcp_auth::TokenProvider;
+use serde::Deserialize;
+
+use crate::config::{comma_separated, Config};
+
+const SECRET_PREFIX: &str = "sm://";
+const SECRET_MANAGER_ENDPOINT: &str = "https://secretmanager.googleapis.com";
+const SECRET_MANAGER_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+#[derive(Debug)]
+pub enum SecretError {
+    /// The value isn't a `sm://projects/{project}/secrets/{secret}/versions/{version}` reference.
+    InvalidReference(String),
+    AuthFailed(gcp_auth::Error),
+    RequestFailed(reqwest::Error),
+    AccessFailed {
+        name: String,
+        status: u16,
+        body: String,
+    },
+    InvalidPayload {
+        name: String,
+        detail: String,
+    },
+}
+
+impl Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretError::InvalidReference(value) => write!(
+                f,
+                "invalid secret reference `{value}`, expected \
+                 sm://projects/{{project}}/secrets/{{secret}}/versions/{{version}}"
+            ),
+            SecretError::AuthFailed(err) => write!(f, "authentication failed: {err}"),
+            SecretError::RequestFailed(err) => write!(f, "request failed: {err}"),
+            SecretError::AccessFailed { name, status, body } => {
+                write!(f, "accessing {name} failed with HTTP {status}: {body}")
+            }
+            SecretError::InvalidPayload { name, detail } => {
+                write!(f, "{name} has an invalid payload: {detail}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+impl From<gcp_auth::Error> for SecretError {
+    fn from(err: gcp_auth::Error) -> Self {
+        SecretError::AuthFailed(err)
+    }
+}
+
+impl From<reqwest::Error> for SecretError {
+    fn from(err: reqwest::Error) -> Self {
+        SecretError::RequestFailed(err)
+    }
+}
+
+/// Resolves `sm://projects/.../secrets/.../versions/...` references to the
+/// value of the secret version in Secret Manager. Other values are returned
+/// as-is.
+///
+/// When `SECRET_MANAGER_EMULATOR_HOST` is set, requests are sent to the
+/// emulator without authentication.
+pub async fn resolve_secret(client: &reqwest::Client, value: &str) -> Result<String, SecretError> {
+    let Some(name) = value.strip_prefix(SECRET_PREFIX) else {
+        return Ok(value.to_string());
+    };
+
+    SecretManager::from_env(client.clone()).await?.access(name).await
+}
+
+/// Resolves the secrets in `config` which are read from environment
+/// variables: `SLACK_WEBHOOK`, `SLACK_BOT_TOKEN`, `SLACK_SIGNING_SECRET`,
+/// `API_TOKEN`, `SMTP_PASSWORD` and `GITHUB_TOKEN`.
+pub async fn resolve_config_secrets(
+    client: &reqwest::Client,
+    config: &mut Config,
+) -> Result<(), SecretError> {
+    let smtp_password = config.smtp.as_mut().and_then(|smtp| smtp.password.as_mut());
+    let github_token = config.github.as_mut().map(|github| &mut github.token);
+    // A secret may hold several comma separated webhooks itself
+    let mut slack_webhooks = vec![];
+    for webhook in &config.slack_webhooks {
+        slack_webhooks.extend(comma_separated(&resolve_secret(client, webhook).await?));
+    }
+    config.slack_webhooks = slack_webhooks;
+
+    for secret in [
+        config.slack_bot_token.as_mut(),
+        config.slack_signing_secret.as_mut(),
+        config.api_token.as_mut(),
+        smtp_password,
+        github_token,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        *secret = resolve_secret(client, secret).await?;
+    }
+
+    Ok(())
+}
+
+struct SecretManager {
+    client: reqwest::Client,
+    endpoint: String,
+    auth: Option<Arc<dyn TokenProvider>>,
+}
+
+impl SecretManager {
+    async fn from_env(client: reqwest::Client) -> Result<Self, SecretError> {
+        let (endpoint, auth) = match std::env::var("SECRET_MANAGER_EMULATOR_HOST") {
+            Ok(host) => (format!("http://{host}"), None),
+            _ => (SECRET_MANAGER_ENDPOINT.to_string(), Some(gcp_auth::provider().await?)),
+        };
+
+        Ok(Self { client, endpoint, auth })
+    }
+
+    /// Accesses the secret version `name`, which is
+    /// `projects/{project}/secrets/{secret}/versions/{version}`.
+    async fn access(&self, name: &str) -> Result<String, SecretError> {
+        #[derive(Deserialize)]
+        struct AccessSecretVersionResponse {
+            payload: SecretPayload,
+        }
+
+        #[derive(Deserialize)]
+        struct SecretPayload {
+            data: String,
+        }
+
+        if !is_secret_version_name(name) {
+            return Err(SecretError::InvalidReference(format!("{SECRET_PREFIX}{name}")));
+        }
+
+        let mut request = self.client.get(format!("{}/v1/{name}:access", self.endpoint));
+        if let Some(auth) = &self.auth {
+            let token = auth.token(&[SECRET_MANAGER_SCOPE]).await?;
+            request = request.bearer_auth(token.as_str());
+        }
+
+        let resp = request.send().await?;
+        let status = resp.status();
+        let text = resp.text().await?;
+        if !status.is_success() {
+            let (name, status) = (name.to_string(), status.as_u16());
+            return Err(SecretError::AccessFailed { name, status, body: text });
+        }
+
+        let invalid_payload =
+            |detail: String| SecretError::InvalidPayload { name: name.to_string(), detail };
+        let response = serde_json::from_str::<AccessSecretVersionResponse>(&text)
+            .map_err(|e| invalid_payload(e.to_string()))?;
+        let data = BASE64_STANDARD
+            .decode(response.payload.data)
+            .map_err(|e| invalid_payload(e.to_string()))?;
+
+        String::from_utf8(data).map_err(|e| invalid_payload(e.to_string()))
+    }
+}
+
+fn is_secret_version_name(name: &str) -> bool {
+    let segments: Vec<_> = name.split('/').collect();
+    match segments[..] {
+        ["projects", project, "secrets", secret, "versions", version] => {
+            !project.is_empty() && !secret.is_empty() && !version.is_empty()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const NAME: &str = "projects/test-project/secrets/slack-webhook/versions/latest";
+
+    fn secret_manager(server: &MockServer) -> SecretManager {
+        SecretManager { client: reqwest::Client::new(), endpoint: server.uri(), auth: None }
+    }
+
+    #[tokio::test]
+    async fn plain_value() {
+        let webhook = "https://hooks.slack.com/services/T000/B000/XXXX";
+        assert_eq!(resolve_secret(&reqwest::Client::new(), webhook).await.unwrap(), webhook);
+    }
+
+    #[tokio::test]
+    async fn access() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/v1/{NAME}:access")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": "projects/0123456789/secrets/slack-webhook/versions/1",
+                "payload": { "data": BASE64_STANDARD.encode("https://hooks.slack.com/services/T000/B000/XXXX") },
+            })))
+            .mount(&server)
+            .await;
+
+        let value = secret_manager(&server).access(NAME).await.unwrap();
+        assert_eq!(value, "https://hooks.slack.com/services/T000/B000/XXXX");
+    }
+
+    #[tokio::test]
+    async fn access_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("NOT_FOUND"))
+            .mount(&server)
+            .await;
+
+        match secret_manager(&server).access(NAME).await {
+            Err(SecretError::AccessFailed { status: 404, .. }) => {}
+            res => panic!("expected SecretError::AccessFailed, got {res:?}"),
+        }
+
+        match secret_manager(&server).access("projects/test-project/secrets/slack-webhook").await {
+            Err(SecretError::InvalidReference(_)) => {}
+            res => panic!("expected SecretError::InvalidReference, got {res:?}"),
+        }
+    }
+
+    /// Resolves `SECRET_MANAGER_TEST_SECRET` (an `sm://` reference) using the
+    /// emulator at `SECRET_MANAGER_EMULATOR_HOST`.
+    #[tokio::test]
+    #[ignore = "requires a Secret Manager emulator"]
+    async fn emulator() {
+        let secret = std::env::var("SECRET_MANAGER_TEST_SECRET").unwrap();
+        assert!(secret.starts_with(SECRET_PREFIX));
+
+        let value = resolve_secret(&reqwest::Client::new(), &secret).await.unwrap();
+        assert!(!value.is_empty());
+    }
+}
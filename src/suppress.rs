@@ -0,0 +1,231 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::message::attributes::payload::EVENT_TYPES;
+use crate::message::attributes::Attributes;
+use crate::AppState;
+
+/// A rule keeping messages for matching clusters out of Slack, managed at
+/// runtime through `/api/v1/suppress`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SuppressRule {
+    /// Assigned when the rule is created.
+    #[serde(default)]
+    pub id: u64,
+
+    /// The cluster names the rule applies to, where `*` matches any
+    /// characters and `?` a single character, e.g. `dev-*`.
+    pub cluster_glob: String,
+
+    /// The event types the rule applies to (e.g. `UpgradeAvailableEvent`),
+    /// all event types when empty.
+    #[serde(default)]
+    pub event_types: Vec<String>,
+
+    /// When set, the rule no longer applies after this time.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl SuppressRule {
+    fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+
+    fn matches(&self, attr: &Attributes) -> bool {
+        glob_matches(&self.cluster_glob, &attr.cluster_name)
+            && (self.event_types.is_empty()
+                || self.event_types.iter().any(|event_type| event_type == attr.event_type()))
+    }
+}
+
+/// The suppression rules, which are kept in memory and lost on restart.
+/// Expired rules are dropped when rules are added or removed.
+#[derive(Clone, Debug, Default)]
+pub struct SuppressionRules {
+    rules: Arc<RwLock<Vec<SuppressRule>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl SuppressionRules {
+    /// Adds the rule with a new `id`, returning the rule as added.
+    pub fn add(&self, rule: SuppressRule) -> SuppressRule {
+        let rule = SuppressRule { id: self.next_id.fetch_add(1, Ordering::Relaxed) + 1, ..rule };
+        let mut rules = self.rules.write().unwrap();
+        rules.retain(|rule| rule.is_active_at(Utc::now()));
+        rules.push(rule.clone());
+        rule
+    }
+
+    /// Removes the rule, returning whether it existed.
+    pub fn remove(&self, id: u64) -> bool {
+        let mut rules = self.rules.write().unwrap();
+        rules.retain(|rule| rule.is_active_at(Utc::now()));
+        let len = rules.len();
+        rules.retain(|rule| rule.id != id);
+        rules.len() < len
+    }
+
+    /// The rules which haven't expired.
+    pub fn active(&self) -> Vec<SuppressRule> {
+        let now = Utc::now();
+        self.rules.read().unwrap().iter().filter(|rule| rule.is_active_at(now)).cloned().collect()
+    }
+
+    /// The first active rule matching the message, if any.
+    pub fn matching(&self, attr: &Attributes) -> Option<SuppressRule> {
+        let now = Utc::now();
+        self.rules
+            .read()
+            .unwrap()
+            .iter()
+            .find(|rule| rule.is_active_at(now) && rule.matches(attr))
+            .cloned()
+    }
+}
+
+/// Lists the active suppression rules (`GET /api/v1/suppress`).
+pub async fn list_rules(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({ "rules": state.suppressions.active() }))
+}
+
+/// Adds a suppression rule (`POST /api/v1/suppress`), responding with the
+/// rule including its `id`. Rules with unknown event types, which would
+/// never match, are rejected with HTTP 422.
+pub async fn create_rule(
+    State(state): State<AppState>,
+    Json(rule): Json<SuppressRule>,
+) -> Response {
+    if let Some(event_type) = rule.event_types.iter().find(|t| !EVENT_TYPES.contains(&t.as_str())) {
+        let detail = format!("Unknown event type `{event_type}`, expected one of {EVENT_TYPES:?}");
+        let body = json!({ "error": "invalid_event_type", "detail": detail });
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+    }
+
+    (StatusCode::CREATED, Json(state.suppressions.add(rule))).into_response()
+}
+
+/// Removes a suppression rule (`DELETE /api/v1/suppress/{id}`).
+pub async fn delete_rule(State(state): State<AppState>, Path(id): Path<u64>) -> StatusCode {
+    match state.suppressions.remove(id) {
+        true => StatusCode::NO_CONTENT,
+        false => StatusCode::NOT_FOUND,
+    }
+}
+
+/// Matches `text` against a glob `pattern` with `*` and `?` wildcards.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    // The position of the last `*` in the pattern and the text it matched up to
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    t = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::attributes::payload::Payload;
+    use chrono::TimeDelta;
+
+    fn rule(cluster_glob: &str, event_types: &[&str]) -> SuppressRule {
+        SuppressRule {
+            id: 0,
+            cluster_glob: cluster_glob.to_string(),
+            event_types: event_types.iter().map(|t| t.to_string()).collect(),
+            expires_at: None,
+        }
+    }
+
+    fn attributes(cluster_name: &str, event_type: &str) -> Attributes {
+        Attributes {
+            cluster_name: cluster_name.to_string(),
+            type_url: format!("type.googleapis.com/google.container.v1beta1.{event_type}"),
+            payload: Payload::UnknownType("{}".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn glob() {
+        for (pattern, text, expected) in [
+            ("test-cluster", "test-cluster", true),
+            ("test-cluster", "test-cluster-2", false),
+            ("*", "", true),
+            ("*", "anything", true),
+            ("dev-*", "dev-us-central1", true),
+            ("dev-*", "prod-us-central1", false),
+            ("*-central?", "prod-us-central1", true),
+            ("*-central?", "prod-us-central12", false),
+            ("*a*b", "xaxxab", true),
+            ("*a*b", "xaxxa", false),
+            ("", "", true),
+            ("", "test-cluster", false),
+        ] {
+            assert_eq!(glob_matches(pattern, text), expected, "{pattern} {text}");
+        }
+    }
+
+    #[test]
+    fn matching() {
+        let rules = SuppressionRules::default();
+        let dev = rules.add(rule("dev-*", &[]));
+        rules.add(rule("prod-*", &["UpgradeAvailableEvent"]));
+
+        assert_eq!(rules.matching(&attributes("dev-1", "UpgradeEvent")), Some(dev));
+        assert!(rules.matching(&attributes("prod-1", "UpgradeAvailableEvent")).is_some());
+        assert!(rules.matching(&attributes("prod-1", "UpgradeEvent")).is_none());
+        assert!(rules.matching(&attributes("staging-1", "UpgradeEvent")).is_none());
+    }
+
+    #[test]
+    fn expired() {
+        let rules = SuppressionRules::default();
+        let expired =
+            SuppressRule { expires_at: Some(Utc::now() - TimeDelta::minutes(1)), ..rule("*", &[]) };
+        let active = SuppressRule {
+            expires_at: Some(Utc::now() + TimeDelta::hours(1)),
+            ..rule("dev-*", &[])
+        };
+        let expired = rules.add(expired);
+        let active = rules.add(active);
+
+        assert_eq!(rules.active(), vec![active.clone()]);
+        assert!(rules.matching(&attributes("prod-1", "UpgradeEvent")).is_none());
+        assert!(!rules.remove(expired.id), "expired rules can't be removed");
+        assert!(rules.remove(active.id));
+        assert!(!rules.remove(active.id));
+        assert!(rules.active().is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt::Display;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use lru::LruCache;
+
+use crate::message::attributes::payload::{Payload, ResourceType};
+use crate::message::attributes::Attributes;
+
+const CAPACITY: NonZeroUsize = NonZeroUsize::new(1000).unwrap();
+
+/// A node pool version further from the control plane version than GKE
+/// recommends, see `Attributes::node_pool_version_skew_check`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionSkew {
+    pub node_pool_version: String,
+    pub control_plane_version: String,
+    pub minor_versions: u64,
+}
+
+impl Display for VersionSkew {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "⚠️ *Version Skew* of {} minor versions between node pool version {} and control \
+             plane version {}, GKE recommends keeping node pools within 1 minor version of the \
+             control plane",
+            self.minor_versions, self.node_pool_version, self.control_plane_version
+        )
+    }
+}
+
+/// Remembers the running control plane version of each cluster, as reported
+/// by control plane `UpgradeEvent` and `NodeUpgradeCompleteEvent` messages,
+/// so node pool `UpgradeAvailableEvent` messages can be checked for version
+/// skew.
+#[derive(Clone, Debug)]
+pub struct ControlPlaneVersions {
+    versions: Arc<Mutex<LruCache<String, String>>>,
+}
+
+impl Default for ControlPlaneVersions {
+    fn default() -> Self {
+        Self { versions: Arc::new(Mutex::new(LruCache::new(CAPACITY))) }
+    }
+}
+
+impl ControlPlaneVersions {
+    /// Records the running version of a control plane, i.e. the current
+    /// version of an `UpgradeEvent` until the upgrade completes, or checks
+    /// a node pool `UpgradeAvailableEvent` against it.
+    pub fn record(&self, attr: &Attributes) -> Option<VersionSkew> {
+        let mut versions = self.versions.lock().unwrap();
+        let running = match &attr.payload {
+            Payload::UpgradeEvent(p) => Some((&p.resource_type, &p.current_version)),
+            Payload::NodeUpgradeCompleteEvent(p) => Some((&p.resource_type, &p.target_version)),
+            _ => None,
+        };
+        if let Some((ResourceType::ControlPlane, version)) = running {
+            versions.put(cluster(attr), version.clone());
+            return None;
+        }
+
+        let p = attr.payload.as_upgrade_available_event()?;
+        let control_plane_version = versions.get(&cluster(attr))?;
+        let minor_versions = attr.node_pool_version_skew_check(control_plane_version)?;
+        Some(VersionSkew {
+            node_pool_version: p.version.clone(),
+            control_plane_version: control_plane_version.clone(),
+            minor_versions,
+        })
+    }
+
+    /// The running control plane version of the cluster of a message, if a
+    /// control plane `UpgradeEvent` or `NodeUpgradeCompleteEvent` has been
+    /// received for it.
+    pub fn get(&self, attr: &Attributes) -> Option<String> {
+        self.versions.lock().unwrap().get(&cluster(attr)).cloned()
+    }
+}
+
+/// Remembers when an `UpgradeAvailableEvent` for a version was first
+/// received for each cluster, so `UpgradeEvent` messages can show how long
+/// the target version was available before the upgrade started.
+#[derive(Clone, Debug)]
+pub struct AvailableVersions {
+    available: Arc<Mutex<LruCache<String, DateTime<Utc>>>>,
+}
+
+impl Default for AvailableVersions {
+    fn default() -> Self {
+        Self { available: Arc::new(Mutex::new(LruCache::new(CAPACITY))) }
+    }
+}
+
+impl AvailableVersions {
+    /// Records when the version of an `UpgradeAvailableEvent` became
+    /// available, keeping the first time as GKE sends an event for each node
+    /// pool and repeats them.
+    pub fn record(&self, attr: &Attributes, at: DateTime<Utc>) {
+        if let Some(p) = attr.payload.as_upgrade_available_event() {
+            let key = format!("{}/{}", cluster(attr), p.version);
+            self.available.lock().unwrap().get_or_insert(key, || at);
+        }
+    }
+
+    /// How long the target version of an `UpgradeEvent` had been available
+    /// when the upgrade started.
+    pub fn available_for(&self, attr: &Attributes, started: DateTime<Utc>) -> Option<TimeDelta> {
+        let p = attr.payload.as_upgrade_event()?;
+        let key = format!("{}/{}", cluster(attr), p.target_version);
+        let available = *self.available.lock().unwrap().get(&key)?;
+        Some(started - available).filter(|available_for| *available_for >= TimeDelta::zero())
+    }
+}
+
+fn cluster(attr: &Attributes) -> String {
+    format!(
+        "projects/{}/locations/{}/clusters/{}",
+        attr.project_id, attr.cluster_location, attr.cluster_name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::attributes::payload::{
+        NodeUpgradeCompleteEvent, UpgradeAvailableEvent, UpgradeEvent,
+    };
+
+    fn attributes(cluster_name: &str, payload: Payload) -> Attributes {
+        Attributes {
+            project_id: "0123456789".to_string(),
+            cluster_name: cluster_name.to_string(),
+            cluster_location: "us-central1".to_string(),
+            payload,
+            ..Default::default()
+        }
+    }
+
+    fn upgrade_available(
+        cluster_name: &str,
+        resource_type: ResourceType,
+        version: &str,
+    ) -> Attributes {
+        let payload = Payload::UpgradeAvailableEvent(UpgradeAvailableEvent {
+            resource_type,
+            version: version.to_string(),
+            ..Default::default()
+        });
+        attributes(cluster_name, payload)
+    }
+
+    #[test]
+    fn record() {
+        let versions = ControlPlaneVersions::default();
+        let node_pool = |version| {
+            versions.record(&upgrade_available("cluster-a", ResourceType::NodePool, version))
+        };
+        let control_plane_upgrade = |current_version: &str, target_version: &str| {
+            let payload = Payload::UpgradeEvent(UpgradeEvent {
+                resource_type: ResourceType::ControlPlane,
+                current_version: current_version.to_string(),
+                target_version: target_version.to_string(),
+                ..Default::default()
+            });
+            versions.record(&attributes("cluster-a", payload))
+        };
+
+        assert_eq!(node_pool("1.27.3-gke.100"), None, "control plane version unknown");
+        assert_eq!(
+            versions.get(&upgrade_available("cluster-a", ResourceType::NodePool, "1.27.3-gke.100")),
+            None
+        );
+
+        let available = upgrade_available("cluster-a", ResourceType::ControlPlane, "1.30.1-gke.1");
+        assert_eq!(versions.record(&available), None);
+        assert_eq!(node_pool("1.27.3-gke.100"), None, "available versions aren't running");
+
+        assert_eq!(control_plane_upgrade("1.28.1-gke.200", "1.29.4-gke.300"), None);
+        assert_eq!(node_pool("1.27.3-gke.100"), None, "1 minor version is fine");
+
+        let complete = Payload::NodeUpgradeCompleteEvent(NodeUpgradeCompleteEvent {
+            resource_type: ResourceType::ControlPlane,
+            current_version: "1.28.1-gke.200".to_string(),
+            target_version: "1.29.4-gke.300".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(versions.record(&attributes("cluster-a", complete)), None);
+        assert_eq!(
+            node_pool("1.27.3-gke.100"),
+            Some(VersionSkew {
+                node_pool_version: "1.27.3-gke.100".to_string(),
+                control_plane_version: "1.29.4-gke.300".to_string(),
+                minor_versions: 2,
+            })
+        );
+        assert_eq!(node_pool("1.31.0-gke.100"), None, "node pools ahead aren't skewed");
+        assert_eq!(
+            versions
+                .get(&upgrade_available("cluster-a", ResourceType::NodePool, "1.27.3-gke.100"))
+                .as_deref(),
+            Some("1.29.4-gke.300")
+        );
+        assert_eq!(
+            versions.record(&upgrade_available(
+                "cluster-b",
+                ResourceType::NodePool,
+                "1.27.3-gke.100"
+            )),
+            None,
+            "other clusters aren't affected"
+        );
+    }
+
+    #[test]
+    fn available_for() {
+        let versions = AvailableVersions::default();
+        let at = |time| DateTime::parse_from_rfc3339(time).unwrap().to_utc();
+        let upgrade = |cluster_name, target_version: &str| Attributes {
+            project_id: "0123456789".to_string(),
+            cluster_name: String::from(cluster_name),
+            cluster_location: "us-central1".to_string(),
+            payload: Payload::UpgradeEvent(UpgradeEvent {
+                target_version: target_version.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let started = at("2024-03-11T08:00:00Z");
+
+        let available = upgrade_available("cluster-a", ResourceType::NodePool, "1.28.1-gke.200");
+        assert_eq!(versions.available_for(&upgrade("cluster-a", "1.28.1-gke.200"), started), None);
+        versions.record(&available, at("2024-03-01T08:00:00Z"));
+        versions.record(&available, at("2024-03-05T08:00:00Z"));
+
+        assert_eq!(
+            versions.available_for(&upgrade("cluster-a", "1.28.1-gke.200"), started),
+            Some(TimeDelta::days(10)),
+            "since the first event"
+        );
+        assert_eq!(versions.available_for(&upgrade("cluster-a", "1.28.2-gke.100"), started), None);
+        assert_eq!(versions.available_for(&upgrade("cluster-b", "1.28.1-gke.200"), started), None);
+        assert_eq!(
+            versions
+                .available_for(&upgrade("cluster-a", "1.28.1-gke.200"), at("2024-02-01T00:00:00Z")),
+            None,
+            "started before it was available"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+
+use serde::Deserialize;
+
+use crate::error::AppError;
+use crate::message::notifier::NotifierConfig;
+
+/// A webhook messages are posted to, as configured in `WEBHOOK_CONFIG_FILE`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct WebhookConfig {
+    pub url: String,
+
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// The `Content-Type` of the posted JSON, when the webhook requires one.
+    #[serde(default)]
+    pub content_type: Option<String>,
+}
+
+impl From<&WebhookConfig> for NotifierConfig {
+    fn from(webhook: &WebhookConfig) -> Self {
+        NotifierConfig {
+            headers: webhook.headers.clone(),
+            content_type: webhook.content_type.clone(),
+            ..NotifierConfig::new(webhook.url.clone())
+        }
+    }
+}
+
+/// The named webhooks messages are posted to, read at startup from the JSON
+/// file at `WEBHOOK_CONFIG_FILE`, e.g.
+///
+/// ```json
+/// {
+///   "platform-team": { "url": "https://hooks.slack.com/services/..." },
+///   "alerts": { "url": "https://alerts.example.com/gke", "headers": { "X-Api-Key": "..." } }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct WebhookRegistry {
+    /// The webhooks by name, in the order of their names.
+    webhooks: Vec<(String, NotifierConfig)>,
+}
+
+impl WebhookRegistry {
+    /// Reads the registry from `WEBHOOK_CONFIG_FILE`, or returns an empty
+    /// registry when it isn't set.
+    pub fn from_env() -> Result<Self, AppError> {
+        let Ok(path) = env::var("WEBHOOK_CONFIG_FILE") else {
+            return Ok(WebhookRegistry::default());
+        };
+
+        let json = std::fs::read_to_string(&path).map_err(|err| {
+            AppError::InvalidConfig(format!("WEBHOOK_CONFIG_FILE {path} can't be read: {err}"))
+        })?;
+        WebhookRegistry::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, AppError> {
+        let webhooks: BTreeMap<String, WebhookConfig> =
+            serde_json::from_str(json).map_err(|err| {
+                AppError::InvalidConfig(format!("WEBHOOK_CONFIG_FILE is invalid: {err}"))
+            })?;
+        let webhooks = webhooks.iter().map(|(name, webhook)| (name.clone(), webhook.into()));
+        Ok(WebhookRegistry { webhooks: webhooks.collect() })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.webhooks.is_empty()
+    }
+
+    /// The webhooks as named destinations for `dispatch_all_notifiers`.
+    pub fn destinations(&self) -> &[(String, NotifierConfig)] {
+        &self.webhooks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_json() {
+        let registry = WebhookRegistry::from_json(
+            r#"{
+                "platform-team": { "url": "https://hooks.slack.com/services/T000/B000/XXXX" },
+                "alerts": {
+                    "url": "https://alerts.example.com/gke",
+                    "headers": { "X-Api-Key": "secret" },
+                    "content_type": "application/json"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let webhooks = registry.destinations();
+        assert_eq!(webhooks.len(), 2);
+        assert_eq!(webhooks[0].0, "alerts");
+        assert_eq!(webhooks[0].1.headers["X-Api-Key"], "secret");
+        assert_eq!(webhooks[0].1.content_type.as_deref(), Some("application/json"));
+        assert_eq!(webhooks[1].0, "platform-team");
+        assert!(webhooks[1].1.headers.is_empty());
+
+        assert!(WebhookRegistry::from_json("{}").unwrap().is_empty());
+        match WebhookRegistry::from_json(r#"{ "alerts": {} }"#) {
+            Err(AppError::InvalidConfig(_)) => {}
+            res => panic!("expected AppError::InvalidConfig, got {res:?}"),
+        }
+    }
+}